@@ -0,0 +1,288 @@
+//! L'analyse d'après-partie : chaque coup joué est comparé au meilleur
+//! coup de la recherche alpha-beta, à la même profondeur.
+use std::{
+    fs,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    engine::Engine,
+    error::P4Error,
+    input::Input,
+    negamax::{Negamax, WIN_SCORE},
+    player::PlayerID,
+};
+
+/// Ce que vaut un coup face au meilleur
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Verdict {
+    /// Aussi bon que le meilleur coup
+    Best,
+    /// Moins bon, sans changer l'issue de la partie
+    Inaccuracy,
+    /// Laisse filer une victoire, offre une défaite, ou perd au moins `Analysis::BLUNDER_SWING`
+    Blunder,
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Best => write!(f, "meilleur coup"),
+            Verdict::Inaccuracy => write!(f, "imprécision"),
+            Verdict::Blunder => write!(f, "gaffe"),
+        }
+    }
+}
+
+/// Un score du point de vue du joueur qui a le trait
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Eval(pub i32);
+
+impl Eval {
+    /// Le nombre de coups du joueur avant sa victoire, ou de l'adversaire avant la sienne
+    fn forced(&self) -> Option<i32> {
+        let plies = WIN_SCORE - self.0.abs();
+        (plies < 64).then_some((plies + 1) / 2)
+    }
+}
+
+impl std::fmt::Display for Eval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.forced() {
+            Some(n) if self.0 > 0 => write!(f, "gagne en {}", n),
+            Some(n) => write!(f, "perd en {}", n),
+            None => write!(f, "{:+}", self.0),
+        }
+    }
+}
+
+/// Un coup de la partie, jugé
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoveReport {
+    pub player: PlayerID,
+    pub played: Input,
+    pub eval: Eval,
+    /// Le meilleur coup, qui peut être celui joué
+    pub best: Input,
+    pub best_eval: Eval,
+    pub verdict: Verdict,
+}
+
+impl MoveReport {
+    /// Ce que le coup joué a perdu par rapport au meilleur
+    pub fn swing(&self) -> i32 {
+        self.best_eval.0 - self.eval.0
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Analysis {
+    depth: u8,
+    pub moves: Vec<MoveReport>,
+}
+
+impl Analysis {
+    /// Un écart d'une ligne de trois jetons
+    pub const BLUNDER_SWING: i32 = 64;
+    pub const DEFAULT_DEPTH: u8 = 4;
+    /// Au-delà, un seul coup peut prendre plusieurs secondes
+    pub const DEPTHS: RangeInclusive<u8> = 1..=6;
+    /// Le temps laissé à chaque coup, assez large pour ne jamais couper la recherche
+    /// jusqu'à la plus grande profondeur de `DEPTHS`
+    const TIME_PER_MOVE: Duration = Duration::from_secs(60);
+
+    /// Rejoue la partie de `engine` depuis le début. Comme `Negamax`,
+    /// seulement à deux sur le cube standard
+    pub fn of_game(engine: &Engine, depth: u8) -> Result<Analysis, P4Error> {
+        let dims = engine.plate().dimensions();
+        if !dims.is_standard() {
+            return Err(P4Error::UnsupportedDimensions(dims));
+        }
+        if engine.turn_order().count() > 2 {
+            return Err(P4Error::UnsupportedPlayers);
+        }
+        let mut replay = Engine::with_players(dims, engine.turn_order());
+        let mut moves = Vec::new();
+        for played in engine.history() {
+            let player = replay.current_player();
+            let scores =
+                Negamax::new(player, depth, Self::TIME_PER_MOVE).score_moves(replay.plate())?;
+            let (best, best_score) = scores
+                .iter()
+                .max_by_key(|(_, s)| *s)
+                .cloned()
+                .ok_or(P4Error::NoLegalMove)?;
+            let score = scores
+                .iter()
+                .find(|(mv, _)| mv == played)
+                .map(|(_, s)| *s)
+                .ok_or_else(|| P4Error::InvalidArgument(format!("{} was not legal", played)))?;
+            let (eval, best_eval) = (Eval(score), Eval(best_score));
+            moves.push(MoveReport {
+                player,
+                played: played.clone(),
+                eval,
+                // à score égal, le coup joué était un meilleur coup
+                best: if score == best_score {
+                    played.clone()
+                } else {
+                    best
+                },
+                best_eval,
+                verdict: Self::judge(eval, best_eval),
+            });
+            replay.play(played.clone())?;
+        }
+        log::info!("Game analysed at depth {} : {} moves", depth, moves.len());
+        Ok(Analysis { depth, moves })
+    }
+
+    fn judge(eval: Eval, best: Eval) -> Verdict {
+        // une victoire plus lente reste une victoire, une défaite plus rapide
+        // reste la défaite qui attendait déjà le joueur
+        let lost_win =
+            best.0 > 0 && best.forced().is_some() && !(eval.0 > 0 && eval.forced().is_some());
+        let gave_loss = eval.forced().is_some()
+            && eval.0 < 0
+            && eval < best
+            && !(best.0 < 0 && best.forced().is_some());
+        if eval == best {
+            Verdict::Best
+        } else if lost_win || gave_loss || best.0 - eval.0 >= Self::BLUNDER_SWING {
+            Verdict::Blunder
+        } else {
+            Verdict::Inaccuracy
+        }
+    }
+
+    /// Le rapport s'écrit à côté de la partie : `last_game.p4` donne `last_game.analysis.txt`
+    pub fn report_path(save_path: &Path) -> PathBuf {
+        save_path.with_extension("analysis.txt")
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), P4Error> {
+        let path = path.as_ref();
+        fs::write(path, self.to_string())
+            .map_err(|e| P4Error::FileError(path.display().to_string(), e))
+    }
+}
+
+impl std::fmt::Display for Analysis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Analyse de la partie (profondeur {})", self.depth)?;
+        for (i, mv) in self.moves.iter().enumerate() {
+            let mut line = format!(
+                "{:>3}. {:?} {:<4} {:<14} {:<12}",
+                i + 1,
+                mv.player,
+                mv.played.to_string(),
+                mv.verdict.to_string(),
+                mv.eval.to_string()
+            );
+            if mv.verdict != Verdict::Best {
+                // l'écart avec une victoire forcée n'a pas de sens
+                if mv.eval.forced().is_none() && mv.best_eval.forced().is_none() {
+                    line += &format!(" écart {},", mv.swing());
+                }
+                line += &format!(" mieux : {} ({})", mv.best, mv.best_eval);
+            }
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        let mut players: Vec<PlayerID> = self.moves.iter().map(|mv| mv.player).collect();
+        players.sort_by_key(|p| p.index());
+        players.dedup();
+        for player in players {
+            let count = |verdict| {
+                self.moves
+                    .iter()
+                    .filter(|mv| mv.player == player && mv.verdict == verdict)
+                    .count()
+            };
+            writeln!(
+                f,
+                "{:?} : {} meilleurs coups, {} imprécisions, {} gaffes",
+                player,
+                count(Verdict::Best),
+                count(Verdict::Inaccuracy),
+                count(Verdict::Blunder)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missed_wins_are_blunders() {
+        // P1 pouvait gagner en D0 au 7e coup, joue ailleurs, puis gagne quand même
+        let engine = Engine::from_notation("A0 A1 B0 B1 C0 C1 D3 A2 D0").unwrap();
+        let analysis = Analysis::of_game(&engine, 2).unwrap();
+        assert_eq!(analysis.moves.len(), 9);
+        let missed = &analysis.moves[6];
+        assert_eq!(missed.player, PlayerID::P1);
+        assert_eq!(missed.verdict, Verdict::Blunder);
+        assert_eq!(missed.best.to_string(), "D0");
+        assert_eq!(missed.best_eval.to_string(), "gagne en 1");
+        assert!(missed.swing() > 0);
+        // P2 gagnait à son tour en D1, et laisse D0 ouvert
+        assert_eq!(analysis.moves[7].verdict, Verdict::Blunder);
+        assert_eq!(analysis.moves[7].best.to_string(), "D1");
+        assert_eq!(analysis.moves[8].verdict, Verdict::Best);
+        assert_eq!(analysis.moves[8].eval.to_string(), "gagne en 1");
+    }
+
+    #[test]
+    fn slower_wins_are_inaccuracies() {
+        // P1 menace en D0 et en D2 : B3 remet la victoire à plus tard sans la perdre
+        let engine = Engine::from_notation("A0 D1 B0 A0 C0 D3 A2 C0 B2 A2 C2 B2 B3").unwrap();
+        let slower = &Analysis::of_game(&engine, 3).unwrap().moves[12];
+        assert_eq!(slower.eval.to_string(), "gagne en 2");
+        assert_eq!(slower.best_eval.to_string(), "gagne en 1");
+        assert_eq!(slower.verdict, Verdict::Inaccuracy);
+    }
+
+    #[test]
+    fn faster_losses_are_inaccuracies() {
+        let (in_three, in_one) = (Eval(-(WIN_SCORE - 6)), Eval(-(WIN_SCORE - 2)));
+        assert_eq!(in_three.to_string(), "perd en 3");
+        assert_eq!(in_one.to_string(), "perd en 1");
+        // la partie était déjà perdue
+        assert_eq!(Analysis::judge(in_one, in_three), Verdict::Inaccuracy);
+        // elle ne l'était pas
+        assert_eq!(Analysis::judge(in_one, Eval(0)), Verdict::Blunder);
+    }
+
+    #[test]
+    fn report_is_written_next_to_the_game() {
+        assert_eq!(
+            Analysis::report_path(Path::new("log/last_game.p4")),
+            PathBuf::from("log/last_game.analysis.txt")
+        );
+        let engine = Engine::from_notation("B1 C2 A0").unwrap();
+        let analysis = Analysis::of_game(&engine, 2).unwrap();
+        let path = std::env::temp_dir().join(format!("p4_analysis_{}.txt", std::process::id()));
+        analysis.save(&path).unwrap();
+        let report = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(report.starts_with("Analyse de la partie (profondeur 2)"));
+        assert_eq!(report.lines().count(), 1 + 3 + 2);
+        assert!(report.lines().nth(1).unwrap().starts_with("  1. P1 B1"));
+    }
+
+    #[test]
+    fn needs_the_standard_duel() {
+        let engine = Engine::with_players(
+            crate::dimensions::Dimensions::STANDARD,
+            crate::player::TurnOrder::new(3).unwrap(),
+        );
+        assert!(matches!(
+            Analysis::of_game(&engine, 2),
+            Err(P4Error::UnsupportedPlayers)
+        ));
+    }
+}
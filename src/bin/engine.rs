@@ -0,0 +1,62 @@
+//! Un joueur intégré exposé par le protocole des moteurs : les commandes
+//! arrivent sur l'entrée standard, les réponses partent sur la sortie standard.
+use std::io;
+
+use puissance::{EngineAdapter, P4Error, PlayerKind};
+
+const HELP: &str = "\
+Usage : p4_engine [OPTIONS]
+
+  --player <JOUEUR>      le joueur qui répond (défaut : negamax:4)
+  --seed <N>             graine des bots aléatoires
+  -h, --help             affiche cette aide
+
+Les joueurs sont ceux de my_app, sauf human : random, tactical, negamax[:P[:MS]],
+mcts[:N|:MSms[:JEU]] et engine[:MS]:<COMMANDE> ; go movetime borne le temps
+de negamax et de mcts.";
+
+fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<EngineAdapter>, P4Error> {
+    let mut kind: PlayerKind = "negamax:4".parse()?;
+    let mut seed = rand::random();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| P4Error::InvalidArgument(format!("{} needs a value", arg)))
+        };
+        match arg.as_str() {
+            "--player" => kind = value()?.parse()?,
+            "--seed" => {
+                let v = value()?;
+                seed = v
+                    .parse()
+                    .map_err(|_| P4Error::InvalidArgument(format!("Invalid seed {}", v)))?;
+            }
+            "-h" | "--help" => return Ok(None),
+            _ => {
+                return Err(P4Error::InvalidArgument(format!(
+                    "Unknown argument {}",
+                    arg
+                )))
+            }
+        }
+    }
+    EngineAdapter::new(kind, seed).map(Some)
+}
+
+fn main() {
+    let adapter = match parse(std::env::args().skip(1)) {
+        Ok(Some(adapter)) => adapter,
+        Ok(None) => {
+            println!("{}", HELP);
+            return;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, HELP);
+            std::process::exit(2);
+        }
+    };
+    if let Err(e) = adapter.run(io::stdin().lock(), io::stdout().lock()) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
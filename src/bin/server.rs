@@ -0,0 +1,97 @@
+//! Serveur d'une partie en réseau : il attend un client par joueur, arbitre
+//! les coups et les relaie aux autres joueurs et aux spectateurs.
+use std::{net::TcpListener, str::FromStr};
+
+use puissance::{
+    serve, Dimensions, Outcome, P4Error, TurnOrder, DEFAULT_ADDRESS, DEFAULT_SPECTATOR_ADDRESS,
+};
+
+const HELP: &str = "\
+Usage : p4_server [OPTIONS]
+
+  --listen <ADRESSE>     adresse d'écoute (défaut : 127.0.0.1:4444)
+  --spectators <ADRESSE> adresse d'écoute des spectateurs (défaut : 127.0.0.1:4445)
+  --players <N>          nombre de joueurs, de 2 à 4 (défaut : 2)
+  --size <LxNxH[:A]>     taille du plateau et longueur des lignes (défaut : 4x4x4:4)
+  -h, --help             affiche cette aide";
+
+struct Options {
+    address: String,
+    spectators: String,
+    players: TurnOrder,
+    dims: Dimensions,
+}
+
+fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, P4Error> {
+    let mut options = Options {
+        address: DEFAULT_ADDRESS.to_owned(),
+        spectators: DEFAULT_SPECTATOR_ADDRESS.to_owned(),
+        players: TurnOrder::default(),
+        dims: Dimensions::default(),
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| P4Error::InvalidArgument(format!("{} needs a value", arg)))
+        };
+        match arg.as_str() {
+            "--listen" => options.address = value()?,
+            "--spectators" => options.spectators = value()?,
+            "--players" => {
+                let v = value()?;
+                let count = v
+                    .parse()
+                    .map_err(|_| P4Error::InvalidArgument(format!("Invalid player count {}", v)))?;
+                options.players = TurnOrder::new(count)?;
+            }
+            "--size" => options.dims = Dimensions::from_str(&value()?)?,
+            "-h" | "--help" => return Ok(None),
+            _ => {
+                return Err(P4Error::InvalidArgument(format!(
+                    "Unknown argument {}",
+                    arg
+                )))
+            }
+        }
+    }
+    Ok(Some(options))
+}
+
+fn bind(address: &str) -> TcpListener {
+    TcpListener::bind(address).unwrap_or_else(|e| {
+        eprintln!("{} : {}", address, P4Error::NetworkError(e));
+        std::process::exit(1);
+    })
+}
+
+fn main() {
+    let options = match parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", HELP);
+            return;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, HELP);
+            std::process::exit(2);
+        }
+    };
+    let listener = bind(&options.address);
+    let spectators = bind(&options.spectators);
+    println!(
+        "En attente de {} joueurs sur {} (plateau {}), spectateurs sur {}",
+        options.players.count(),
+        options.address,
+        options.dims,
+        options.spectators
+    );
+    match serve(&listener, Some(spectators), options.dims, options.players) {
+        Ok(Outcome::Win(player, _)) => println!("Partie terminée : victoire de {:?}", player),
+        Ok(Outcome::Draw) => println!("Partie terminée : match nul"),
+        Ok(Outcome::Ongoing) => println!("Partie interrompue"),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
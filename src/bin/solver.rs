@@ -0,0 +1,133 @@
+//! Résolution exacte des positions du cube standard, et construction du livre d'ouvertures.
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use puissance::{Engine, OpeningBook, P4Error, Solver};
+
+const HELP: &str = "\
+Usage : p4_solver [OPTIONS]
+
+  --position <COUPS>     la partie à résoudre, par exemple \"A0 B2 A0\" (défaut : vide)
+  --book <FICHIER>       le livre d'ouvertures à utiliser, ou à écrire avec --plies
+  --plies <N>            construit le livre des positions d'au plus N coups
+  --budget <MS>          temps maximal d'une résolution (défaut : illimité)
+  -h, --help             affiche cette aide
+
+Seul le plateau 4x4x4 à deux joueurs peut être résolu.";
+
+struct Options {
+    position: String,
+    book: Option<PathBuf>,
+    plies: Option<u8>,
+    budget: Option<Duration>,
+}
+
+fn number<T: FromStr>(s: String) -> Result<T, P4Error> {
+    s.parse()
+        .map_err(|_| P4Error::InvalidArgument(format!("{} is not a valid number", s)))
+}
+
+fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, P4Error> {
+    let mut options = Options {
+        position: String::new(),
+        book: None,
+        plies: None,
+        budget: None,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| P4Error::InvalidArgument(format!("{} needs a value", arg)))
+        };
+        match arg.as_str() {
+            "--position" => options.position = value()?,
+            "--book" => options.book = Some(PathBuf::from(value()?)),
+            "--plies" => options.plies = Some(number(value()?)?),
+            "--budget" => options.budget = Some(Duration::from_millis(number(value()?)?)),
+            "-h" | "--help" => return Ok(None),
+            _ => {
+                return Err(P4Error::InvalidArgument(format!(
+                    "Unknown argument {}",
+                    arg
+                )))
+            }
+        }
+    }
+    if options.plies.is_some() && options.book.is_none() {
+        return Err(P4Error::InvalidArgument(String::from(
+            "--plies needs --book to write the book",
+        )));
+    }
+    Ok(Some(options))
+}
+
+fn file_error(path: &Path) -> impl FnOnce(std::io::Error) -> P4Error + '_ {
+    move |e| P4Error::FileError(path.display().to_string(), e)
+}
+
+fn build_book(mut solver: Solver, path: &Path, plies: u8) -> Result<(), P4Error> {
+    let mut file = fs::File::create(path).map_err(file_error(path))?;
+    let mut written = Ok(());
+    let entries = solver.build_book(plies, |entry| {
+        println!("{}", entry);
+        if written.is_ok() {
+            written = writeln!(file, "{}", entry);
+        }
+    });
+    written.map_err(file_error(path))?;
+    println!(
+        "{} positions écrites dans {}",
+        entries.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+fn run(options: Options) -> Result<(), P4Error> {
+    let mut solver = Solver::default();
+    if let Some(budget) = options.budget {
+        solver = solver.with_budget(budget);
+    }
+    if let (Some(path), Some(plies)) = (&options.book, options.plies) {
+        return build_book(solver, path, plies);
+    }
+    if let Some(path) = &options.book {
+        let book: OpeningBook = fs::read_to_string(path)
+            .map_err(file_error(path))?
+            .parse()?;
+        solver = solver.with_book(book);
+    }
+    let engine = Engine::from_notation(&options.position)?;
+    let solution = solver.solve(engine.plate())?;
+    println!(
+        "{:?} a le trait : {}, meilleur coup {} ({} positions)",
+        engine.current_player(),
+        solution.value,
+        solution.best,
+        solver.nodes()
+    );
+    Ok(())
+}
+
+fn main() {
+    let options = match parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", HELP);
+            return;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, HELP);
+            std::process::exit(2);
+        }
+    };
+    if let Err(e) = run(options) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
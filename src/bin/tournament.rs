@@ -0,0 +1,121 @@
+//! Tournoi sans affichage entre deux joueurs, pour comparer les bots.
+use std::{fs, path::PathBuf, str::FromStr};
+
+use puissance::{Dimensions, P4Error, PlayerKind, Tournament};
+
+const HELP: &str = "\
+Usage : p4_tournament [OPTIONS]
+
+  --p1 <JOUEUR>          le premier joueur (défaut : negamax:4)
+  --p2 <JOUEUR>          le second joueur (défaut : random)
+  --games <N>            nombre de parties, chacun commence la moitié (défaut : 10)
+  --size <LxNxH[:A]>     taille du plateau et longueur des lignes (défaut : 4x4x4:4)
+  --seed <N>             graine des bots aléatoires
+  --csv <FICHIER>        écrit une ligne par partie
+  --json <FICHIER>       écrit le bilan et les parties
+  -h, --help             affiche cette aide
+
+Les joueurs sont ceux de my_app, sauf human.";
+
+struct Options {
+    players: [PlayerKind; 2],
+    games: usize,
+    dims: Dimensions,
+    seed: u64,
+    csv: Option<PathBuf>,
+    json: Option<PathBuf>,
+}
+
+fn number<T: FromStr>(s: String) -> Result<T, P4Error> {
+    s.parse()
+        .map_err(|_| P4Error::InvalidArgument(format!("{} is not a valid number", s)))
+}
+
+fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, P4Error> {
+    let mut options = Options {
+        players: [PlayerKind::from_str("negamax:4")?, PlayerKind::Random],
+        games: 10,
+        dims: Dimensions::default(),
+        seed: rand::random(),
+        csv: None,
+        json: None,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| P4Error::InvalidArgument(format!("{} needs a value", arg)))
+        };
+        match arg.as_str() {
+            "--p1" => options.players[0] = value()?.parse()?,
+            "--p2" => options.players[1] = value()?.parse()?,
+            "--games" => options.games = number(value()?)?,
+            "--size" => options.dims = value()?.parse()?,
+            "--seed" => options.seed = number(value()?)?,
+            "--csv" => options.csv = Some(PathBuf::from(value()?)),
+            "--json" => options.json = Some(PathBuf::from(value()?)),
+            "-h" | "--help" => return Ok(None),
+            _ => {
+                return Err(P4Error::InvalidArgument(format!(
+                    "Unknown argument {}",
+                    arg
+                )))
+            }
+        }
+    }
+    if options.players.contains(&PlayerKind::Human) {
+        return Err(P4Error::InvalidArgument(String::from(
+            "a tournament has no human player",
+        )));
+    }
+    Ok(Some(options))
+}
+
+fn write(path: &PathBuf, content: String) -> Result<(), P4Error> {
+    fs::write(path, content).map_err(|e| P4Error::FileError(path.display().to_string(), e))
+}
+
+fn run(options: Options) -> Result<(), P4Error> {
+    let tournament = Tournament::new(options.players, options.dims, options.seed);
+    println!(
+        "{} contre {}, {} parties (graine {})",
+        tournament.players()[0],
+        tournament.players()[1],
+        options.games,
+        options.seed
+    );
+    let mut n = 0;
+    let standings = tournament.run(options.games, |game| {
+        n += 1;
+        let winner = match game.winner {
+            Some(p) => tournament.players()[p].to_string(),
+            None => String::from("nul"),
+        };
+        println!("Partie {:>4} : {} en {} coups", n, winner, game.length);
+    })?;
+    println!("\n{}", standings);
+    if let Some(path) = &options.csv {
+        write(path, standings.to_csv())?;
+    }
+    if let Some(path) = &options.json {
+        write(path, standings.to_json())?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let options = match parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", HELP);
+            return;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, HELP);
+            std::process::exit(2);
+        }
+    };
+    if let Err(e) = run(options) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,272 @@
+use crate::{
+    coord::{Coord, NumberCoord},
+    dimensions::DIRECTIONS,
+    error::P4Error,
+    plate::{Line, Plate},
+    player::PlayerID,
+};
+
+const fn in_cube(v: i8) -> bool {
+    v >= 0 && v < 4
+}
+
+/// Le bit de la case (x, y, hauteur)
+pub const fn cell_index(x: u8, y: u8, h: u8) -> u8 {
+    x + 4 * y + 16 * h
+}
+
+const fn compute_line_masks() -> [u64; 76] {
+    let mut masks = [0u64; 76];
+    let mut n = 0;
+    let mut d = 0;
+    while d < DIRECTIONS.len() {
+        let (dx, dy, dz) = DIRECTIONS[d];
+        let mut start = 0;
+        while start < 64 {
+            let (x, y, z) = ((start % 4) as i8, (start / 4 % 4) as i8, (start / 16) as i8);
+            let (ex, ey, ez) = (x + 3 * dx, y + 3 * dy, z + 3 * dz);
+            if in_cube(ex) && in_cube(ey) && in_cube(ez) {
+                let mut mask = 0u64;
+                let mut i = 0;
+                while i < 4 {
+                    let c = cell_index((x + i * dx) as u8, (y + i * dy) as u8, (z + i * dz) as u8);
+                    mask |= 1 << c;
+                    i += 1;
+                }
+                masks[n] = mask;
+                n += 1;
+            }
+            start += 1;
+        }
+        d += 1;
+    }
+    masks
+}
+
+const fn compute_lines_by_cell() -> [u128; 64] {
+    let mut res = [0u128; 64];
+    let mut l = 0;
+    while l < 76 {
+        let mut c = 0;
+        while c < 64 {
+            if LINE_MASKS[l] & (1 << c) != 0 {
+                res[c] |= 1 << l;
+            }
+            c += 1;
+        }
+        l += 1;
+    }
+    res
+}
+
+/// Masques des 76 lignes gagnantes du cube
+pub const LINE_MASKS: [u64; 76] = compute_line_masks();
+/// Pour chaque case, l'ensemble des lignes (indices dans `LINE_MASKS`) qui la traversent
+const LINES_BY_CELL: [u128; 64] = compute_lines_by_cell();
+
+/// Le cube standard sous forme d'un `u64` par joueur, bit `x + 4 * y + 16 * hauteur`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Bitboard {
+    players: [u64; 2],
+    heights: [u8; 16],
+}
+
+impl Bitboard {
+    fn index(player: PlayerID) -> usize {
+        match player {
+            PlayerID::P1 => 0,
+            PlayerID::P2 => 1,
+            PlayerID::P3 | PlayerID::P4 => panic!("A bitboard only holds two players"),
+            PlayerID::Empty => panic!("{:?}", P4Error::EmptyPlayerPlayed),
+        }
+    }
+
+    pub fn get_player_board(&self, player: PlayerID) -> u64 {
+        self.players[Self::index(player)]
+    }
+
+    pub fn occupied(&self) -> u64 {
+        self.players[0] | self.players[1]
+    }
+
+    pub fn get_height(&self, pillar: u8) -> u8 {
+        self.heights[pillar as usize]
+    }
+
+    pub fn get_cell(&self, cell: u8) -> PlayerID {
+        let bit = 1 << cell;
+        if self.players[0] & bit != 0 {
+            PlayerID::P1
+        } else if self.players[1] & bit != 0 {
+            PlayerID::P2
+        } else {
+            PlayerID::Empty
+        }
+    }
+
+    pub fn playable(&self, pillar: u8) -> bool {
+        self.heights[pillar as usize] < 4
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.occupied() == u64::MAX
+    }
+
+    pub fn legal_pillars(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..16).filter(|p| self.playable(*p))
+    }
+
+    /// Pose un jeton en haut du pilier et renvoie la case occupée
+    pub fn play(&mut self, pillar: u8, player: PlayerID) -> Result<u8, P4Error> {
+        if !self.playable(pillar) {
+            return Err(P4Error::OverFilledPillar(Some(
+                NumberCoord::new(pillar).as_letter_coord(),
+            )));
+        }
+        let cell = pillar + 16 * self.heights[pillar as usize];
+        self.players[Self::index(player)] |= 1 << cell;
+        self.heights[pillar as usize] += 1;
+        Ok(cell)
+    }
+
+    /// Retire le jeton du haut du pilier
+    pub fn undo(&mut self, pillar: u8) {
+        debug_assert!(self.heights[pillar as usize] > 0);
+        self.heights[pillar as usize] -= 1;
+        let cell = pillar + 16 * self.heights[pillar as usize];
+        self.players[0] &= !(1 << cell);
+        self.players[1] &= !(1 << cell);
+    }
+
+    /// Les indices des lignes passant par `cell`
+    pub fn lines_through(cell: u8) -> impl Iterator<Item = usize> {
+        let mut lines = LINES_BY_CELL[cell as usize];
+        std::iter::from_fn(move || {
+            (lines != 0).then(|| {
+                let l = lines.trailing_zeros() as usize;
+                lines &= lines - 1;
+                l
+            })
+        })
+    }
+
+    /// Le joueur de la case `cell` y a-t-il complété une ligne ?
+    pub fn wins_at(&self, cell: u8) -> bool {
+        let player = self.get_cell(cell);
+        if player == PlayerID::Empty {
+            return false;
+        }
+        let board = self.get_player_board(player);
+        Self::lines_through(cell).any(|l| board & LINE_MASKS[l] == LINE_MASKS[l])
+    }
+
+    pub fn winning_line(&self) -> Option<(PlayerID, Line)> {
+        for player in [PlayerID::P1, PlayerID::P2] {
+            let board = self.get_player_board(player);
+            if let Some(mask) = LINE_MASKS.iter().find(|m| board & **m == **m) {
+                let mut line = vec![(0, 0, 0); 4];
+                let mut bits = *mask;
+                for cell in line.iter_mut() {
+                    let c = bits.trailing_zeros() as u8;
+                    bits &= bits - 1;
+                    *cell = (c % 4, c / 4 % 4, c / 16);
+                }
+                return Some((player, line));
+            }
+        }
+        None
+    }
+}
+
+impl From<&Plate> for Bitboard {
+    fn from(plt: &Plate) -> Self {
+        assert!(
+            plt.dimensions().is_standard(),
+            "A bitboard only holds a 4x4x4 plate, not {}",
+            plt.dimensions()
+        );
+        let mut res = Bitboard::default();
+        for pillar in 0..16u8 {
+            for h in 0..4 {
+                match plt.get_cell((pillar % 4, pillar / 4, h)) {
+                    PlayerID::Empty => break,
+                    p => {
+                        res.play(pillar, p).expect("A pillar has at most 4 tokens");
+                    }
+                }
+            }
+        }
+        res
+    }
+}
+
+impl From<&Bitboard> for Plate {
+    fn from(bb: &Bitboard) -> Self {
+        let mut res = Plate::default();
+        for pillar in 0..16u8 {
+            for h in 0..bb.get_height(pillar) {
+                res.add_player(NumberCoord::new(pillar), bb.get_cell(pillar + 16 * h))
+                    .expect("A pillar has at most 4 tokens");
+            }
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::TurnOrder;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn masks_match_plate_lines() {
+        let mut from_plate: Vec<u64> = Plate::default()
+            .lines()
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .fold(0, |m, &(x, y, h)| m | 1 << cell_index(x, y, h))
+            })
+            .collect();
+        let mut masks = LINE_MASKS.to_vec();
+        from_plate.sort();
+        masks.sort();
+        assert_eq!(from_plate, masks);
+    }
+
+    #[test]
+    fn random_games_agree_with_plate() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..50 {
+            let mut bb = Bitboard::default();
+            let mut plt = Plate::default();
+            let mut player = PlayerID::P1;
+            while !bb.is_full() {
+                let moves: Vec<u8> = bb.legal_pillars().collect();
+                let pillar = moves[rng.gen_range(0..moves.len())];
+                let cell = bb.play(pillar, player).unwrap();
+                plt.add_player(NumberCoord::new(pillar), player).unwrap();
+                assert_eq!(Bitboard::from(&plt), bb);
+                assert_eq!(bb.wins_at(cell), plt.check_win());
+                if bb.wins_at(cell) {
+                    assert_eq!(bb.winning_line().map(|w| w.0), Some(player));
+                    break;
+                }
+                player = TurnOrder::default().next(player);
+            }
+            let back = Plate::from(&bb);
+            assert_eq!(Bitboard::from(&back), bb);
+        }
+    }
+
+    #[test]
+    fn undo_restores_the_board() {
+        let mut bb = Bitboard::default();
+        bb.play(3, PlayerID::P1).unwrap();
+        let before = bb;
+        bb.play(3, PlayerID::P2).unwrap();
+        bb.undo(3);
+        assert_eq!(bb, before);
+    }
+}
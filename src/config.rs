@@ -0,0 +1,671 @@
+use std::{path::PathBuf, str::FromStr, time::Duration};
+
+use crate::{
+    analysis::Analysis,
+    dimensions::Dimensions,
+    error::P4Error,
+    logic::{Human, RBot, Thinker},
+    mcts::{Budget, Mcts, Playout},
+    negamax::Negamax,
+    net::{DEFAULT_ADDRESS, DEFAULT_SPECTATOR_ADDRESS},
+    plate::TypeOfDisplay,
+    player::{PlayerID, TurnOrder},
+    protocol::ProcessThinker,
+    tactical::Tactical,
+};
+
+pub const HELP: &str = "\
+Puissance 4 en 3D
+
+Usage : my_app [OPTIONS]
+
+Options :
+  --players <N>          nombre de joueurs, de 2 à 4 (défaut : 2)
+  --p1 .. --p4 <JOUEUR>  les joueurs (défaut : human, negamax:4, random, random) ;
+                         sans eux, un menu demande le niveau des bots au lancement
+  --hotseat              que des humains sur le même terminal
+  --name1 .. --name4 <NOM>
+                         le nom d'un joueur humain
+  --size <LxNxH[:A]>     taille du plateau et nombre de jetons à aligner (défaut : 4x4x4:4) ;
+                         une partie enregistrée garde la sienne
+  --first <N>            le joueur qui commence, il prend la première couleur (défaut : 1)
+  --display <VUE>        vue de départ : victor (par niveau) ou arthur (par pilier)
+  --log-config <FICHIER> configuration du logger (défaut : logger_config.yaml)
+  --seed <N>             graine des bots aléatoires, tirée au hasard sinon ;
+                         celle de chaque partie est notée dans le log
+  --connect [ADRESSE]    rejoint une partie de p4_server en jouant --p1 (défaut : 127.0.0.1:4444)
+  --watch [ADRESSE]      regarde une partie de p4_server sans jouer (défaut : 127.0.0.1:4445),
+                         jusqu'à Échap ou q
+  --resume <FICHIER>     reprend une partie enregistrée, qui continue de s'y enregistrer
+  --no-save              n'enregistre pas la partie, réécrite sinon après chaque coup
+                         dans log/last_game.p4 (log/last_network_game.p4 en réseau)
+  --replay <FICHIER>     revoit une partie enregistrée
+  --replay-log [LOG]     revoit la dernière partie d'un log (défaut : log/my.log)
+  --analyse [P]          à la fin, juge chaque coup avec une recherche de profondeur P,
+                         de 1 à 6 (défaut : 4), et écrit le rapport à côté de la partie
+  -h, --help             affiche cette aide
+
+Joueurs :
+  human                  au clavier
+  random                 coups au hasard
+  tactical               gagne ou bloque en un coup, sinon vise les lignes ouvertes
+  negamax[:P[:MS]]       recherche alpha-beta de profondeur P (défaut 4)
+                         limitée à MS millisecondes (défaut 2000)
+  mcts[:N|:MSms[:JEU]]   recherche Monte-Carlo de N itérations ou de MS millisecondes
+                         (défaut 2000ms), parties simulées au hasard (random) ou
+                         en gagnant et bloquant en un coup (light, par défaut)
+  engine[:MS]:<COMMANDE> un moteur externe lancé par COMMANDE, qui parle le protocole
+                         de p4_engine, MS millisecondes par coup (défaut 2000)";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlayerKind {
+    Human,
+    Random,
+    Tactical,
+    Negamax {
+        depth: u8,
+        time_budget: Duration,
+    },
+    Mcts {
+        budget: Budget,
+        playout: Playout,
+    },
+    /// Un programme lancé à part, voir `protocol`
+    Engine {
+        command: Vec<String>,
+        time_budget: Duration,
+    },
+}
+
+impl PlayerKind {
+    pub fn build(
+        &self,
+        seat: PlayerID,
+        players: TurnOrder,
+        seed: u64,
+        name: Option<String>,
+    ) -> Result<Box<dyn Thinker>, P4Error> {
+        Ok(match self {
+            PlayerKind::Human => Box::new(Human::new(seat).with_name(name).with_players(players)),
+            PlayerKind::Random => Box::new(RBot::new(seed)),
+            PlayerKind::Tactical => Box::new(Tactical::new(seat, players, seed)),
+            PlayerKind::Negamax { depth, time_budget } => {
+                Box::new(Negamax::new(seat, *depth, *time_budget))
+            }
+            PlayerKind::Mcts { budget, playout } => {
+                Box::new(Mcts::new(seat, players, *budget, *playout, seed))
+            }
+            PlayerKind::Engine {
+                command,
+                time_budget,
+            } => Box::new(ProcessThinker::new(command, players, *time_budget)?),
+        })
+    }
+}
+
+fn parse_number<T: FromStr>(s: &str, what: &str) -> Result<T, P4Error> {
+    s.parse()
+        .map_err(|_| P4Error::InvalidArgument(format!("{} is not a valid {}", s, what)))
+}
+
+impl FromStr for PlayerKind {
+    type Err = P4Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // la commande d'un moteur peut contenir des ':'
+        if let Some(rest) = s.strip_prefix("engine:") {
+            let (millis, command) = match rest.split_once(':') {
+                Some((ms, command)) if ms.bytes().all(|b| b.is_ascii_digit()) => {
+                    (parse_number(ms, "time budget")?, command)
+                }
+                _ => (2000, rest),
+            };
+            let command: Vec<String> = command.split_whitespace().map(String::from).collect();
+            if command.is_empty() {
+                return Err(P4Error::InvalidArgument(String::from(
+                    "an engine needs a command to run",
+                )));
+            }
+            return Ok(PlayerKind::Engine {
+                command,
+                time_budget: Duration::from_millis(millis),
+            });
+        }
+        let mut parts = s.split(':');
+        let kind = match parts.next().unwrap_or_default() {
+            "human" => PlayerKind::Human,
+            "random" => PlayerKind::Random,
+            "tactical" => PlayerKind::Tactical,
+            "negamax" => {
+                let depth = match parts.next() {
+                    Some(d) => parse_number(d, "search depth")?,
+                    None => 4,
+                };
+                let millis = match parts.next() {
+                    Some(ms) => parse_number(ms, "time budget")?,
+                    None => 2000,
+                };
+                if depth == 0 {
+                    return Err(P4Error::InvalidArgument(String::from(
+                        "the search depth must be at least 1",
+                    )));
+                }
+                PlayerKind::Negamax {
+                    depth,
+                    time_budget: Duration::from_millis(millis),
+                }
+            }
+            "mcts" => {
+                let budget = match parts.next() {
+                    Some(b) => match b.strip_suffix("ms") {
+                        Some(ms) => {
+                            Budget::Time(Duration::from_millis(parse_number(ms, "time budget")?))
+                        }
+                        None => Budget::Iterations(parse_number(b, "iteration count")?),
+                    },
+                    None => Budget::Time(Duration::from_secs(2)),
+                };
+                let playout = match parts.next() {
+                    Some("random") => Playout::Random,
+                    Some("light") | None => Playout::Light,
+                    Some(p) => {
+                        return Err(P4Error::InvalidArgument(format!("unknown playout {}", p)))
+                    }
+                };
+                if budget == Budget::Iterations(0) {
+                    return Err(P4Error::InvalidArgument(String::from(
+                        "the search needs at least one iteration",
+                    )));
+                }
+                PlayerKind::Mcts { budget, playout }
+            }
+            _ => return Err(P4Error::InvalidArgument(format!("unknown player {}", s))),
+        };
+        if parts.next().is_some() {
+            return Err(P4Error::InvalidArgument(format!(
+                "too many levels for player {}",
+                s
+            )));
+        }
+        Ok(kind)
+    }
+}
+
+impl std::fmt::Display for PlayerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlayerKind::Human => write!(f, "human"),
+            PlayerKind::Random => write!(f, "random"),
+            PlayerKind::Tactical => write!(f, "tactical"),
+            PlayerKind::Negamax { depth, time_budget } => {
+                write!(f, "negamax:{}:{}", depth, time_budget.as_millis())
+            }
+            PlayerKind::Mcts { budget, playout } => write!(f, "mcts:{}:{}", budget, playout),
+            PlayerKind::Engine {
+                command,
+                time_budget,
+            } => write!(
+                f,
+                "engine:{}:{}",
+                time_budget.as_millis(),
+                command.join(" ")
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReplaySource {
+    /// Une partie enregistrée en notation
+    Save(PathBuf),
+    Log(PathBuf),
+}
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Un joueur par place, dans l'ordre des options `--p1`, `--p2`...
+    pub players: Vec<PlayerKind>,
+    pub names: Vec<Option<String>>,
+    pub turn_order: TurnOrder,
+    pub dimensions: Dimensions,
+    pub first: PlayerID,
+    pub display: TypeOfDisplay,
+    pub logger_config: PathBuf,
+    pub seed: Option<u64>,
+    pub resume: Option<PathBuf>,
+    /// L'adresse du serveur d'une partie en réseau
+    pub connect: Option<String>,
+    /// L'adresse des spectateurs d'une partie en réseau
+    pub watch: Option<String>,
+    pub replay: Option<ReplaySource>,
+    /// La partie est réécrite après chaque coup
+    pub save: bool,
+    /// La profondeur de l'analyse d'après-partie, s'il y en a une
+    pub analysis: Option<u8>,
+    /// Les bots attendent le niveau choisi dans le menu de départ
+    pub menu: bool,
+    pub help: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            players: vec![
+                PlayerKind::Human,
+                PlayerKind::Negamax {
+                    depth: 4,
+                    time_budget: Duration::from_secs(2),
+                },
+            ],
+            names: vec![None; 2],
+            turn_order: TurnOrder::default(),
+            dimensions: Dimensions::STANDARD,
+            first: PlayerID::P1,
+            display: TypeOfDisplay::Victor,
+            logger_config: PathBuf::from("logger_config.yaml"),
+            seed: None,
+            resume: None,
+            connect: None,
+            watch: None,
+            replay: None,
+            save: true,
+            analysis: None,
+            menu: true,
+            help: false,
+        }
+    }
+}
+
+/// La place désignée par une option comme `--p3` ou `--name3`
+fn seat_of(arg: &str, prefix: &str) -> Option<usize> {
+    let seat: usize = arg.strip_prefix(prefix)?.parse().ok()?;
+    (1..=TurnOrder::MAX_PLAYERS as usize)
+        .contains(&seat)
+        .then_some(seat - 1)
+}
+
+impl Config {
+    pub const DEFAULT_LOG_PATH: &'static str = "log/my.log";
+
+    /// Les arguments sans le nom du programme
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Config, P4Error> {
+        let mut config = Config::default();
+        let seats = TurnOrder::MAX_PLAYERS as usize;
+        let mut players = config.players.clone();
+        players.resize(seats, PlayerKind::Random);
+        let mut names = vec![None; seats];
+        // la dernière place configurée doit exister
+        let mut last_seat = 0;
+        let mut first = 1;
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| P4Error::InvalidArgument(format!("{} needs a value", arg)))
+            };
+            match arg.as_str() {
+                "--players" => {
+                    config.turn_order = TurnOrder::new(parse_number(&value()?, "player count")?)?
+                }
+                s if seat_of(s, "--p").is_some() => {
+                    let seat = seat_of(s, "--p").unwrap_or_default();
+                    players[seat] = value()?.parse()?;
+                    last_seat = last_seat.max(seat);
+                    config.menu = false;
+                }
+                "--hotseat" => {
+                    players.fill(PlayerKind::Human);
+                    config.menu = false;
+                }
+                s if seat_of(s, "--name").is_some() => {
+                    let seat = seat_of(s, "--name").unwrap_or_default();
+                    names[seat] = Some(value()?);
+                    last_seat = last_seat.max(seat);
+                }
+                "--size" => config.dimensions = value()?.parse()?,
+                "--first" => first = parse_number(&value()?, "player")?,
+                "--display" => {
+                    config.display = match value()?.as_str() {
+                        "victor" => TypeOfDisplay::Victor,
+                        "arthur" => TypeOfDisplay::Arthur,
+                        v => {
+                            return Err(P4Error::InvalidArgument(format!(
+                                "--display expects victor or arthur, not {}",
+                                v
+                            )))
+                        }
+                    }
+                }
+                "--log-config" => config.logger_config = PathBuf::from(value()?),
+                "--seed" => config.seed = Some(parse_number(&value()?, "seed")?),
+                "--resume" => config.resume = Some(PathBuf::from(value()?)),
+                "--no-save" => config.save = false,
+                "--replay" => config.replay = Some(ReplaySource::Save(PathBuf::from(value()?))),
+                "--connect" => {
+                    config.connect = Some(match args.peek() {
+                        Some(a) if !a.starts_with('-') => args.next().unwrap_or_default(),
+                        _ => DEFAULT_ADDRESS.to_owned(),
+                    })
+                }
+                "--watch" => {
+                    config.watch = Some(match args.peek() {
+                        Some(a) if !a.starts_with('-') => args.next().unwrap_or_default(),
+                        _ => DEFAULT_SPECTATOR_ADDRESS.to_owned(),
+                    })
+                }
+                "--replay-log" => {
+                    let path = match args.peek() {
+                        Some(p) if !p.starts_with('-') => args.next().unwrap_or_default(),
+                        _ => Self::DEFAULT_LOG_PATH.to_owned(),
+                    };
+                    config.replay = Some(ReplaySource::Log(PathBuf::from(path)));
+                }
+                "--analyse" => {
+                    let depth = match args.peek() {
+                        Some(d) if !d.starts_with('-') => {
+                            parse_number(&args.next().unwrap_or_default(), "search depth")?
+                        }
+                        _ => Analysis::DEFAULT_DEPTH,
+                    };
+                    if !Analysis::DEPTHS.contains(&depth) {
+                        return Err(P4Error::InvalidArgument(format!(
+                            "the analysis depth must be between {} and {}",
+                            Analysis::DEPTHS.start(),
+                            Analysis::DEPTHS.end()
+                        )));
+                    }
+                    config.analysis = Some(depth);
+                }
+                "-h" | "--help" => config.help = true,
+                _ => return Err(P4Error::InvalidArgument(format!("unknown option {}", arg))),
+            }
+        }
+        let count = config.turn_order.count() as usize;
+        if last_seat >= count || !(1..=count).contains(&first) {
+            return Err(P4Error::InvalidArgument(format!(
+                "there are only {} players",
+                count
+            )));
+        }
+        players.truncate(count);
+        names.truncate(count);
+        config.first = PlayerID::from_index(first - 1);
+        for (kind, name) in players.iter().zip(names.iter()) {
+            if name.is_some() && *kind != PlayerKind::Human {
+                return Err(P4Error::InvalidArgument(String::from(
+                    "only human players can be named",
+                )));
+            }
+        }
+        config.players = players;
+        config.names = names;
+        config.check_duel()?;
+        let modes = [
+            config.resume.is_some(),
+            config.replay.is_some(),
+            config.connect.is_some(),
+            config.watch.is_some(),
+        ];
+        if modes.iter().filter(|m| **m).count() > 1 {
+            return Err(P4Error::InvalidArgument(String::from(
+                "--resume, --replay, --connect and --watch cannot be used together",
+            )));
+        }
+        Ok(config)
+    }
+
+    /// Negamax et `--analyse` ne jouent qu'à deux sur le cube standard. Tant que le
+    /// menu doit choisir les bots, ceux de la configuration ne comptent pas
+    pub fn check_duel(&self) -> Result<(), P4Error> {
+        let count = self.turn_order.count();
+        if self.dimensions.is_standard() && count == 2 {
+            return Ok(());
+        }
+        let negamax = !self.menu
+            && self
+                .players
+                .iter()
+                .any(|kind| matches!(kind, PlayerKind::Negamax { .. }));
+        if negamax {
+            return Err(P4Error::InvalidArgument(format!(
+                "negamax only plays two-player games on a 4x4x4 plate, not {} players on {}",
+                count, self.dimensions
+            )));
+        }
+        if self.analysis.is_some() {
+            return Err(P4Error::InvalidArgument(format!(
+                "--analyse only reviews two-player games on a 4x4x4 plate, not {} players on {}",
+                count, self.dimensions
+            )));
+        }
+        Ok(())
+    }
+
+    /// La configuration d'une partie déjà commencée, sur `dims` et à `players` :
+    /// comme avec `--players`, les places en plus reviennent à `random`
+    pub fn for_game(&self, dims: Dimensions, players: TurnOrder) -> Config {
+        let count = players.count() as usize;
+        let mut config = self.clone();
+        config.dimensions = dims;
+        config.turn_order = players;
+        config.players.resize(count, PlayerKind::Random);
+        config.names.resize(count, None);
+        if config.first.index() >= count {
+            config.first = PlayerID::P1;
+        }
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Config, P4Error> {
+        Config::from_args(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn defaults_without_arguments() {
+        let config = parse("").unwrap();
+        assert_eq!(config.players[0], PlayerKind::Human);
+        assert_eq!(config.players.len(), 2);
+        assert_eq!(config.first, PlayerID::P1);
+        assert!(config.replay.is_none());
+        assert!(config.menu);
+        assert!(config.save);
+        assert!(!parse("--no-save").unwrap().save);
+        assert!(!parse("--p2 tactical").unwrap().menu);
+        assert!(!parse("--hotseat").unwrap().menu);
+    }
+
+    #[test]
+    fn players_and_options_are_read() {
+        let config =
+            parse("--p1 random --p2 negamax:3:500 --first 2 --seed 42 --replay-log").unwrap();
+        assert_eq!(config.players[0], PlayerKind::Random);
+        assert_eq!(
+            config.players[1],
+            PlayerKind::Negamax {
+                depth: 3,
+                time_budget: Duration::from_millis(500)
+            }
+        );
+        assert_eq!(config.first, PlayerID::P2);
+        assert_eq!(config.seed, Some(42));
+        assert_eq!(
+            config.replay,
+            Some(ReplaySource::Log(PathBuf::from(Config::DEFAULT_LOG_PATH)))
+        );
+    }
+
+    #[test]
+    fn analysis_depth_is_optional() {
+        assert_eq!(parse("").unwrap().analysis, None);
+        assert_eq!(parse("--analyse").unwrap().analysis, Some(4));
+        assert_eq!(parse("--analyse 6 --seed 1").unwrap().analysis, Some(6));
+        assert!(parse("--analyse 0").is_err());
+        assert!(parse("--analyse 7").is_err());
+        let err = parse("--analyse --players 3 --p2 random").unwrap_err();
+        assert!(err.to_string().contains("--analyse"), "{}", err);
+    }
+
+    #[test]
+    fn engines_keep_their_command() {
+        let config = parse("--p2 engine:./p4_engine").unwrap();
+        assert_eq!(
+            config.players[1],
+            PlayerKind::Engine {
+                command: vec![String::from("./p4_engine")],
+                time_budget: Duration::from_secs(2)
+            }
+        );
+        let kind: PlayerKind = "engine:300:p4_engine --player negamax:6:250"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            kind.to_string(),
+            "engine:300:p4_engine --player negamax:6:250"
+        );
+        assert!("engine:".parse::<PlayerKind>().is_err());
+    }
+
+    #[test]
+    fn mcts_budget_and_playout() {
+        assert_eq!(
+            "mcts:500:random".parse::<PlayerKind>().unwrap(),
+            PlayerKind::Mcts {
+                budget: Budget::Iterations(500),
+                playout: Playout::Random
+            }
+        );
+        for kind in ["mcts", "mcts:250ms", "mcts:1000:light"] {
+            let parsed: PlayerKind = kind.parse().unwrap();
+            assert_eq!(parsed.to_string().parse::<PlayerKind>().unwrap(), parsed);
+        }
+        assert_eq!(
+            "mcts".parse::<PlayerKind>().unwrap().to_string(),
+            "mcts:2000ms:light"
+        );
+        // elle joue aussi à plusieurs sur un grand plateau
+        assert!(parse("--players 3 --p2 mcts --p3 mcts:100 --size 5x5x5:4").is_ok());
+        assert!(parse("--players 3 --p2 tactical --p3 random --size 5x5x5:4").is_ok());
+    }
+
+    #[test]
+    fn hotseat_makes_two_named_humans() {
+        let config = parse("--hotseat --name1 Alice --name2 Bob").unwrap();
+        assert_eq!(config.players, vec![PlayerKind::Human; 2]);
+        assert_eq!(config.names[1].as_deref(), Some("Bob"));
+    }
+
+    #[test]
+    fn up_to_four_players() {
+        let config = parse("--players 4 --p2 human --p4 human --name4 Dan --first 3").unwrap();
+        assert_eq!(
+            config.players,
+            [
+                PlayerKind::Human,
+                PlayerKind::Human,
+                PlayerKind::Random,
+                PlayerKind::Human
+            ]
+        );
+        assert_eq!(config.names[3].as_deref(), Some("Dan"));
+        assert_eq!(config.first, PlayerID::P3);
+    }
+
+    #[test]
+    fn resumed_game_sets_plate_and_players() {
+        let config = parse("--hotseat --name2 Bob --resume a.p4").unwrap();
+        let dims = "5x5x5:4".parse().unwrap();
+        let resumed = config.for_game(dims, TurnOrder::new(3).unwrap());
+        assert_eq!(resumed.dimensions, dims);
+        assert_eq!(resumed.turn_order.count(), 3);
+        assert_eq!(
+            resumed.players,
+            [PlayerKind::Human, PlayerKind::Human, PlayerKind::Random]
+        );
+        assert_eq!(resumed.names[1].as_deref(), Some("Bob"));
+        assert_eq!(resumed.names[2], None);
+        assert!(resumed.check_duel().is_ok());
+        // negamax et l'analyse ne reprennent pas une partie à trois
+        for args in ["--p2 negamax:4 --resume a.p4", "--analyse --resume a.p4"] {
+            let resumed = parse(args)
+                .unwrap()
+                .for_game(dims, TurnOrder::new(3).unwrap());
+            assert!(
+                matches!(resumed.check_duel(), Err(P4Error::InvalidArgument(_))),
+                "{}",
+                args
+            );
+        }
+    }
+
+    #[test]
+    fn menu_bots_are_checked_once_chosen() {
+        // le negamax par défaut attend le menu, qui ne le proposera pas
+        let config = parse("--size 5x5x5:4").unwrap();
+        assert!(config.menu);
+        assert!(parse("--players 3").unwrap().check_duel().is_ok());
+        let chosen = Config {
+            menu: false,
+            ..config
+        };
+        assert!(matches!(
+            chosen.check_duel(),
+            Err(P4Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn larger_plate_without_negamax() {
+        let config = parse("--p2 random --size 5x5x5:4").unwrap();
+        assert_eq!(config.dimensions.to_string(), "5x5x5:4");
+    }
+
+    #[test]
+    fn network_addresses_have_defaults() {
+        assert_eq!(
+            parse("--connect").unwrap().connect.as_deref(),
+            Some(DEFAULT_ADDRESS)
+        );
+        let config = parse("--connect 10.0.0.2:5000 --p1 random").unwrap();
+        assert_eq!(config.connect.as_deref(), Some("10.0.0.2:5000"));
+        assert_eq!(config.players[0], PlayerKind::Random);
+        assert_eq!(
+            parse("--watch").unwrap().watch.as_deref(),
+            Some(DEFAULT_SPECTATOR_ADDRESS)
+        );
+    }
+
+    #[test]
+    fn invalid_arguments_are_reported() {
+        for args in [
+            "--p1 robot",
+            "--p2 negamax:0",
+            "--p2 negamax:x",
+            "--p2 mcts:0",
+            "--p2 mcts:100:smart",
+            "--first 3",
+            "--seed",
+            "--display 3d",
+            "--resume a.p4 --replay b.p4",
+            "--connect --replay-log",
+            "--watch --connect",
+            "--p2 random --name2 Bob",
+            "--size 5x5x5:4 --p2 negamax:4",
+            "--p2 random --size 4x4",
+            "--p3 random",
+            "--players 3 --p1 negamax:2",
+            "--players 5 --p2 random",
+            "--players 3 --p2 random --first 4",
+            "--what",
+        ] {
+            assert!(
+                matches!(parse(args), Err(P4Error::InvalidArgument(_))),
+                "{}",
+                args
+            );
+        }
+    }
+}
@@ -1,29 +1,25 @@
 use std::str::FromStr;
 
+use crate::{dimensions::Dimensions, error::P4Error};
+
+/// Les colonnes, jusqu'à `Dimensions::MAX_SIDE`
 #[derive(Clone, Debug, Copy, PartialEq)]
 pub enum Letter {
     A,
     B,
     C,
     D,
+    E,
+    F,
+    G,
+    H,
 }
 
 pub trait Coord: std::fmt::Debug {
-    fn to_num(&self) -> u8;
-    fn to_xy_coord(&self) -> (u16, u16) {
-        let n = self.to_num() as u16;
-        (n % 4, n / 4)
-    }
+    fn to_xy_coord(&self) -> (u16, u16);
     fn to_ln_coord(&self) -> (Letter, u8) {
-        let n = self.to_num();
-        let l = match n % 4 {
-            0 => Letter::A,
-            1 => Letter::B,
-            2 => Letter::C,
-            3 => Letter::D,
-            _ => panic!("Wtf donne moi un bon nb"),
-        };
-        (l, n / 4)
+        let (x, y) = self.to_xy_coord();
+        (Letter::from(x as u8), y as u8)
     }
 
     fn as_letter_coord(&self) -> LetterCoord {
@@ -49,37 +45,41 @@ pub struct LetterCoord {
     number: u8,
 }
 
+/// L'indice d'un pilier, les piliers étant rangés ligne par ligne
 #[derive(Clone, PartialEq)]
 pub struct NumberCoord {
     n: u8,
+    width: u8,
 }
 
 impl Coord for LetterCoord {
-    fn to_num(&self) -> u8 {
-        let (l, n) = self.to_xy_coord();
-        l as u8 + n as u8 * 4
-    }
     fn to_xy_coord(&self) -> (u16, u16) {
-        let l: u16 = match self.letter {
-            Letter::A => 0,
-            Letter::B => 1,
-            Letter::C => 2,
-            Letter::D => 3,
-        };
-        (l, self.number as u16)
+        (self.letter as u16, self.number as u16)
     }
 }
 
 impl Coord for NumberCoord {
-    fn to_num(&self) -> u8 {
-        self.n as u8
+    fn to_xy_coord(&self) -> (u16, u16) {
+        ((self.n % self.width) as u16, (self.n / self.width) as u16)
     }
 }
 
 impl NumberCoord {
+    /// Sur le plateau standard
     pub fn new(n: u8) -> NumberCoord {
-        debug_assert!(n < 16);
-        NumberCoord { n }
+        NumberCoord::with_dimensions(n, Dimensions::STANDARD)
+    }
+
+    pub fn with_dimensions(n: u8, dims: Dimensions) -> NumberCoord {
+        debug_assert!((n as usize) < dims.pillars());
+        NumberCoord {
+            n,
+            width: dims.width(),
+        }
+    }
+
+    pub fn to_num(&self) -> u8 {
+        self.n
     }
 }
 
@@ -96,37 +96,42 @@ impl std::fmt::Debug for LetterCoord {
     }
 }
 
+impl Letter {
+    const ALL: [Letter; Dimensions::MAX_SIDE as usize] = [
+        Letter::A,
+        Letter::B,
+        Letter::C,
+        Letter::D,
+        Letter::E,
+        Letter::F,
+        Letter::G,
+        Letter::H,
+    ];
+
+    /// La lettre d'une touche, en majuscule ou en minuscule
+    pub fn from_char(c: char) -> Option<Letter> {
+        let i = (c.to_ascii_uppercase() as u32).checked_sub('A' as u32)?;
+        Self::ALL.get(i as usize).copied()
+    }
+}
+
 impl std::fmt::Display for Letter {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Letter::A => "A",
-                Letter::B => "B",
-                Letter::C => "C",
-                Letter::D => "D",
-            }
-        )
+        write!(f, "{}", (b'A' + *self as u8) as char)
     }
 }
 
+/// Sur le plateau standard
 impl From<(Letter, u8)> for NumberCoord {
     fn from(value: (Letter, u8)) -> Self {
         debug_assert!(value.1 < 4);
-        let li32 = match value.0 {
-            Letter::A => 0,
-            Letter::B => 1,
-            Letter::C => 2,
-            Letter::D => 3,
-        };
-        NumberCoord::new(value.1 * 4 + li32)
+        NumberCoord::new(value.1 * 4 + value.0 as u8)
     }
 }
 
 impl From<(Letter, u8)> for LetterCoord {
     fn from(value: (Letter, u8)) -> Self {
-        debug_assert!(value.1 < 4);
+        debug_assert!(value.1 < Dimensions::MAX_SIDE);
         LetterCoordBuilder::default()
             .letter(value.0)
             .number(value.1)
@@ -143,25 +148,37 @@ impl From<(Letter, u8)> for LetterCoord {
 
 impl From<u8> for Letter {
     fn from(value: u8) -> Self {
-        match value {
-            0 => Letter::A,
-            1 => Letter::B,
-            2 => Letter::C,
-            3 => Letter::D,
-            _ => unreachable!("Conversion u8 -> Letter :\nvalue : {value}"),
-        }
+        *Self::ALL
+            .get(value as usize)
+            .unwrap_or_else(|| unreachable!("Conversion u8 -> Letter :\nvalue : {value}"))
     }
 }
 
 impl FromStr for Letter {
     type Err = ();
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "A" => Ok(Letter::A),
-            "B" => Ok(Letter::B),
-            "C" => Ok(Letter::C),
-            "D" => Ok(Letter::D),
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_uppercase() => Letter::from_char(c).ok_or(()),
             _ => Err(()),
         }
     }
 }
+
+/// `A0` .. `H7`, comme l'affiche `Debug`. Que la case soit sur le plateau
+/// est vérifié par `Engine`
+impl FromStr for LetterCoord {
+    type Err = P4Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || P4Error::InvalidNotation(s.to_owned());
+        let mut chars = s.chars();
+        let letter = chars.next().ok_or_else(err)?;
+        let letter =
+            Letter::from_str(&letter.to_ascii_uppercase().to_string()).map_err(|_| err())?;
+        let number: u8 = chars.as_str().parse().map_err(|_| err())?;
+        if number >= Dimensions::MAX_SIDE {
+            return Err(err());
+        }
+        Ok(LetterCoord::from((letter, number)))
+    }
+}
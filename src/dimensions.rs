@@ -0,0 +1,202 @@
+use std::str::FromStr;
+
+use crate::{error::P4Error, plate::Line};
+
+/// Les 13 directions de l'espace, à un signe près
+pub(crate) const DIRECTIONS: [(i8, i8, i8); 13] = [
+    (1, 0, 0),
+    (0, 1, 0),
+    (0, 0, 1),
+    (1, 1, 0),
+    (1, -1, 0),
+    (1, 0, 1),
+    (1, 0, -1),
+    (0, 1, 1),
+    (0, 1, -1),
+    (1, 1, 1),
+    (1, 1, -1),
+    (1, -1, 1),
+    (1, -1, -1),
+];
+
+/// Taille du plateau : `width` lettres, `depth` numéros, `height` étages,
+/// et le nombre de jetons à aligner pour gagner
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Dimensions {
+    width: u8,
+    depth: u8,
+    height: u8,
+    align: u8,
+}
+
+impl Default for Dimensions {
+    fn default() -> Self {
+        Dimensions::STANDARD
+    }
+}
+
+impl Dimensions {
+    /// Lettres `A` à `H`, numéros `0` à `7`
+    pub const MAX_SIDE: u8 = 8;
+    /// Le cube 4x4x4 où il faut aligner 4 jetons
+    pub const STANDARD: Dimensions = Dimensions {
+        width: 4,
+        depth: 4,
+        height: 4,
+        align: 4,
+    };
+
+    pub fn new(width: u8, depth: u8, height: u8, align: u8) -> Result<Dimensions, P4Error> {
+        let dims = Dimensions {
+            width,
+            depth,
+            height,
+            align,
+        };
+        let sides = [width, depth, height];
+        if sides.iter().any(|s| *s == 0 || *s > Self::MAX_SIDE) {
+            return Err(P4Error::InvalidArgument(format!(
+                "the sides of {} must be between 1 and {}",
+                dims,
+                Self::MAX_SIDE
+            )));
+        }
+        if align < 2 || sides.iter().all(|s| *s < align) {
+            return Err(P4Error::InvalidArgument(format!(
+                "no line of {} tokens fits in {}",
+                align, dims
+            )));
+        }
+        Ok(dims)
+    }
+
+    pub fn width(&self) -> u8 {
+        self.width
+    }
+
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    pub fn height(&self) -> u8 {
+        self.height
+    }
+
+    pub fn align(&self) -> u8 {
+        self.align
+    }
+
+    pub fn is_standard(&self) -> bool {
+        *self == Self::STANDARD
+    }
+
+    /// Nombre de piliers
+    pub fn pillars(&self) -> usize {
+        self.width as usize * self.depth as usize
+    }
+
+    pub fn contains(&self, x: u8, y: u8) -> bool {
+        x < self.width && y < self.depth
+    }
+
+    /// L'indice du pilier (x, y), les piliers sont rangés ligne par ligne
+    pub fn pillar_index(&self, x: u8, y: u8) -> usize {
+        debug_assert!(self.contains(x, y));
+        x as usize + self.width as usize * y as usize
+    }
+
+    pub fn pillar_xy(&self, i: usize) -> (u8, u8) {
+        debug_assert!(i < self.pillars());
+        (
+            (i % self.width as usize) as u8,
+            (i / self.width as usize) as u8,
+        )
+    }
+
+    /// Toutes les lignes gagnantes, dans l'ordre de `DIRECTIONS`
+    pub fn lines(&self) -> Vec<Line> {
+        let n = self.align as i8;
+        let (w, d, h) = (self.width as i8, self.depth as i8, self.height as i8);
+        let inside =
+            |x: i8, y: i8, z: i8| (0..w).contains(&x) && (0..d).contains(&y) && (0..h).contains(&z);
+        let mut lines = Vec::new();
+        for (dx, dy, dz) in DIRECTIONS {
+            for z in 0..h {
+                for y in 0..d {
+                    for x in 0..w {
+                        if !inside(x + (n - 1) * dx, y + (n - 1) * dy, z + (n - 1) * dz) {
+                            continue;
+                        }
+                        lines.push(
+                            (0..n)
+                                .map(|i| {
+                                    ((x + i * dx) as u8, (y + i * dy) as u8, (z + i * dz) as u8)
+                                })
+                                .collect(),
+                        );
+                    }
+                }
+            }
+        }
+        lines
+    }
+}
+
+/// `4x4x4`, suivi éventuellement du nombre de jetons à aligner : `5x5x5:4`.
+/// Sans ce nombre, il faut aligner autant de jetons que le plus grand côté
+impl FromStr for Dimensions {
+    type Err = P4Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || P4Error::InvalidArgument(format!("{} is not a valid board size", s));
+        let (sides, align) = match s.split_once(':') {
+            Some((sides, align)) => (sides, Some(align)),
+            None => (s, None),
+        };
+        let sides: Vec<u8> = sides
+            .split('x')
+            .map(|side| side.parse().map_err(|_| err()))
+            .collect::<Result<_, _>>()?;
+        let [width, depth, height] = sides[..] else {
+            return Err(err());
+        };
+        let align = match align {
+            Some(a) => a.parse().map_err(|_| err())?,
+            None => width.max(depth).max(height),
+        };
+        Dimensions::new(width, depth, height, align)
+    }
+}
+
+impl std::fmt::Display for Dimensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}x{}x{}:{}",
+            self.width, self.depth, self.height, self.align
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_are_parsed() {
+        assert_eq!("4x4x4".parse::<Dimensions>().unwrap(), Dimensions::STANDARD);
+        let dims: Dimensions = "5x5x5:4".parse().unwrap();
+        assert_eq!((dims.width(), dims.align()), (5, 4));
+        for bad in ["4x4", "4x4x4:5", "9x4x4", "0x4x4", "4x4x4:1", "axbxc"] {
+            assert!(bad.parse::<Dimensions>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn lines_are_counted() {
+        assert_eq!(Dimensions::STANDARD.lines().len(), 76);
+        // 5x5x5, 4 à aligner : deux départs possibles le long de chaque axe parcouru
+        assert_eq!("5x5x5:4".parse::<Dimensions>().unwrap().lines().len(), 302);
+        // un morpion sur un seul étage
+        assert_eq!("3x3x1:3".parse::<Dimensions>().unwrap().lines().len(), 8);
+    }
+}
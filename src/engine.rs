@@ -0,0 +1,306 @@
+use crate::{
+    dimensions::Dimensions,
+    error::P4Error,
+    input::Input,
+    logic::Thinker,
+    notation,
+    plate::{Outcome, Plate},
+    player::{PlayerID, TurnOrder},
+};
+
+/// Règles et ordre des tours, sans aucun affichage.
+/// Le terminal (`Game`) n'est qu'un front-end qui le consomme.
+#[derive(Clone, Debug)]
+pub struct Engine {
+    plateau: Plate,
+    turn_order: TurnOrder,
+    current_player: PlayerID,
+    outcome: Outcome,
+    history: Vec<Input>,
+    /// Coups annulés, le dernier annulé en haut de la pile
+    redo_stack: Vec<Input>,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Engine::new()
+    }
+}
+
+impl Engine {
+    pub fn new() -> Engine {
+        Engine::with_dimensions(Dimensions::STANDARD)
+    }
+
+    pub fn with_dimensions(dims: Dimensions) -> Engine {
+        Engine::with_players(dims, TurnOrder::default())
+    }
+
+    pub fn with_players(dims: Dimensions, turn_order: TurnOrder) -> Engine {
+        Engine {
+            plateau: Plate::new(dims),
+            turn_order,
+            current_player: turn_order.first(),
+            outcome: Outcome::Ongoing,
+            history: Vec::new(),
+            redo_stack: Vec::new(),
+        }
+    }
+
+    /// Rejoue une partie écrite en notation (`A0 B2 ...`)
+    pub fn from_notation(s: &str) -> Result<Engine, P4Error> {
+        let mut engine = Engine::new();
+        engine.play_notation(s)?;
+        Ok(engine)
+    }
+
+    /// Joue à la suite les coups écrits en notation. Elle ne retient ni la
+    /// taille du plateau ni le nombre de joueurs, c'est à l'`Engine` de les connaître
+    pub fn play_notation(&mut self, s: &str) -> Result<Outcome, P4Error> {
+        for mv in notation::parse_notation(s)? {
+            self.play(mv)?;
+        }
+        Ok(self.outcome())
+    }
+
+    pub fn to_notation(&self) -> String {
+        notation::to_notation(&self.history)
+    }
+
+    /// Rejoue une partie enregistrée, sur son plateau et avec ses joueurs
+    pub fn from_game_notation(s: &str) -> Result<Engine, P4Error> {
+        let (dims, players, moves) = notation::parse_game_notation(s)?;
+        let mut engine = Engine::with_players(dims, players);
+        for mv in moves {
+            engine.play(mv)?;
+        }
+        Ok(engine)
+    }
+
+    /// La partie à enregistrer, avec la taille du plateau et le nombre de joueurs
+    pub fn to_game_notation(&self) -> String {
+        notation::to_game_notation(self.plateau.dimensions(), self.turn_order, &self.history)
+    }
+
+    pub fn plate(&self) -> &Plate {
+        &self.plateau
+    }
+
+    pub fn turn_order(&self) -> TurnOrder {
+        self.turn_order
+    }
+
+    pub fn current_player(&self) -> PlayerID {
+        self.current_player
+    }
+
+    pub fn outcome(&self) -> Outcome {
+        self.outcome.clone()
+    }
+
+    pub fn history(&self) -> &[Input] {
+        &self.history
+    }
+
+    pub fn redo_count(&self) -> usize {
+        self.redo_stack.len()
+    }
+
+    pub fn legal_moves(&self) -> Vec<Input> {
+        if self.outcome != Outcome::Ongoing {
+            return Vec::new();
+        }
+        self.plateau.legal_moves().map(|(mv, _)| mv).collect()
+    }
+
+    /// Demande un coup au `Thinker` sans le jouer
+    pub fn ask(&self, thinker: &dyn Thinker) -> Result<Input, P4Error> {
+        thinker.think_after(&self.plateau, &self.history)
+    }
+
+    pub fn play(&mut self, mv: Input) -> Result<Outcome, P4Error> {
+        let outcome = self.apply(mv)?;
+        self.redo_stack.clear();
+        Ok(outcome)
+    }
+
+    fn apply(&mut self, mv: Input) -> Result<Outcome, P4Error> {
+        if self.outcome != Outcome::Ongoing {
+            return Err(P4Error::GameOver);
+        }
+        if !mv.is_valid() || !self.plateau.contains(&mv) {
+            return Err(P4Error::InvalidMove(mv));
+        }
+        self.plateau.add_player(mv.clone(), self.current_player)?;
+        self.history.push(mv);
+        self.outcome = self.plateau.outcome();
+        if self.outcome == Outcome::Ongoing {
+            self.current_player = self.turn_order.next(self.current_player);
+        }
+        Ok(self.outcome.clone())
+    }
+
+    /// Reprend le dernier coup, le joueur qui l'avait joué rejoue
+    pub fn undo(&mut self) -> Result<Input, P4Error> {
+        let mv = self.history.pop().ok_or(P4Error::NothingToUndo)?;
+        self.current_player = self.plateau.undo(mv.clone())?;
+        self.outcome = Outcome::Ongoing;
+        self.redo_stack.push(mv.clone());
+        Ok(mv)
+    }
+
+    /// Rejoue le dernier coup annulé
+    pub fn redo(&mut self) -> Result<Outcome, P4Error> {
+        let mv = self.redo_stack.pop().ok_or(P4Error::NothingToRedo)?;
+        self.apply(mv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn players_alternate() {
+        let mut engine = Engine::new();
+        assert_eq!(engine.current_player(), PlayerID::P1);
+        assert_eq!(engine.legal_moves().len(), 16);
+        assert_eq!(engine.play(Input::from(0)).unwrap(), Outcome::Ongoing);
+        assert_eq!(engine.current_player(), PlayerID::P2);
+    }
+
+    #[test]
+    fn vertical_win_ends_the_game() {
+        let mut engine = Engine::new();
+        for _ in 0..3 {
+            engine.play(Input::from(0)).unwrap();
+            engine.play(Input::from(1)).unwrap();
+        }
+        assert!(matches!(
+            engine.play(Input::from(0)).unwrap(),
+            Outcome::Win(PlayerID::P1, _)
+        ));
+        assert!(engine.legal_moves().is_empty());
+        assert!(matches!(
+            engine.play(Input::from(2)),
+            Err(P4Error::GameOver)
+        ));
+    }
+
+    #[test]
+    fn full_cube_without_line_is_a_draw() {
+        let mut engine = Engine::new();
+        let moves = [
+            3, 0, 0, 1, 0, 2, 0, 2, 1, 1, 3, 1, 4, 2, 2, 3, 4, 3, 4, 4, 5, 5, 6, 5, 5, 7, 6, 7, 6,
+            6, 8, 7, 7, 9, 8, 8, 9, 8, 9, 12, 9, 12, 10, 10, 10, 10, 11, 11, 11, 11, 12, 12, 15,
+            13, 13, 14, 13, 15, 13, 15, 14, 15, 14, 14,
+        ];
+        let (last, moves) = moves.split_last().unwrap();
+        for mv in moves {
+            assert_eq!(engine.play(Input::from(*mv)).unwrap(), Outcome::Ongoing);
+        }
+        assert_eq!(engine.play(Input::from(*last)).unwrap(), Outcome::Draw);
+        assert!(engine.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn undo_and_redo_follow_the_history() {
+        let mut engine = Engine::new();
+        assert!(matches!(engine.undo(), Err(P4Error::NothingToUndo)));
+        for mv in [0, 5, 0] {
+            engine.play(Input::from(mv)).unwrap();
+        }
+        assert_eq!(engine.undo().unwrap(), Input::from(0));
+        assert_eq!(engine.undo().unwrap(), Input::from(5));
+        assert_eq!(engine.current_player(), PlayerID::P2);
+        assert_eq!(engine.history(), &[Input::from(0)]);
+        assert_eq!(engine.plate().get_cell((1, 1, 0)), PlayerID::Empty);
+
+        engine.redo().unwrap();
+        assert_eq!(engine.current_player(), PlayerID::P1);
+        assert_eq!(engine.plate().get_cell((1, 1, 0)), PlayerID::P2);
+
+        // un nouveau coup efface ce qui restait à refaire
+        engine.play(Input::from(3)).unwrap();
+        assert!(matches!(engine.redo(), Err(P4Error::NothingToRedo)));
+    }
+
+    #[test]
+    fn undo_reopens_a_finished_game() {
+        let mut engine = Engine::new();
+        for _ in 0..3 {
+            engine.play(Input::from(0)).unwrap();
+            engine.play(Input::from(1)).unwrap();
+        }
+        engine.play(Input::from(0)).unwrap();
+        engine.undo().unwrap();
+        assert_eq!(engine.outcome(), Outcome::Ongoing);
+        assert_eq!(engine.current_player(), PlayerID::P1);
+        assert!(matches!(
+            engine.redo().unwrap(),
+            Outcome::Win(PlayerID::P1, _)
+        ));
+    }
+
+    #[test]
+    fn full_pillar_is_rejected() {
+        let mut engine = Engine::new();
+        for _ in 0..4 {
+            engine.play(Input::from(5)).unwrap();
+        }
+        assert_eq!(engine.legal_moves().len(), 15);
+        assert!(matches!(
+            engine.play(Input::from(5)),
+            Err(P4Error::OverFilledPillar(_))
+        ));
+        assert!(matches!(
+            engine.play(Input::default()),
+            Err(P4Error::InvalidMove(_))
+        ));
+        assert!(matches!(
+            engine.play(Input::from((4, 0))),
+            Err(P4Error::InvalidMove(_))
+        ));
+    }
+
+    #[test]
+    fn three_players_take_turns() {
+        let order = TurnOrder::new(3).unwrap();
+        let mut engine = Engine::with_players("5x5x5:4".parse().unwrap(), order);
+        // chacun remplit sa ligne, P1 la complète le premier
+        engine.play_notation("A0 A1 A2 B0 B1 B2 C0 C1 C2").unwrap();
+        assert_eq!(engine.current_player(), PlayerID::P1);
+        assert_eq!(engine.plate().get_cell((2, 2, 0)), PlayerID::P3);
+        assert!(matches!(
+            engine.play_notation("D0"),
+            Ok(Outcome::Win(PlayerID::P1, _))
+        ));
+        engine.undo().unwrap();
+        engine.undo().unwrap();
+        assert_eq!(engine.current_player(), PlayerID::P3);
+        // la partie enregistrée se rejoue sans redonner le plateau ni les joueurs
+        let saved = Engine::from_game_notation(&engine.to_game_notation()).unwrap();
+        assert_eq!(saved.plate().dimensions(), engine.plate().dimensions());
+        assert_eq!(saved.turn_order(), order);
+        assert_eq!(saved.history(), engine.history());
+        assert_eq!(saved.current_player(), PlayerID::P3);
+    }
+
+    #[test]
+    fn larger_plate_needs_the_configured_line() {
+        let dims = "5x5x5:4".parse().unwrap();
+        let mut engine = Engine::with_dimensions(dims);
+        engine.play_notation("E0 E1 D0 D1 C0 C1").unwrap();
+        assert_eq!(engine.legal_moves().len(), 25);
+        assert!(matches!(
+            engine.play(Input::from((1, 0))),
+            Ok(Outcome::Win(PlayerID::P1, line)) if line.len() == 4
+        ));
+        // E4 existe sur ce plateau mais pas sur le cube standard
+        assert!(Engine::with_dimensions(dims).play_notation("E4").is_ok());
+        assert!(matches!(
+            Engine::from_notation("E4"),
+            Err(P4Error::InvalidMove(_))
+        ));
+    }
+}
@@ -1,10 +1,30 @@
-use crate::coord::LetterCoord;
+use crate::{coord::LetterCoord, dimensions::Dimensions, input::Input, player::PlayerID};
 
 #[derive(Debug)]
 pub enum P4Error {
     OutputInterfaceError(std::io::Error),
     OverFilledPillar(Option<LetterCoord>), //Box<dyn Coord>>),
     EmptyPlayerPlayed,
+    InvalidMove(Input),
+    GameOver,
+    NoLegalMove,
+    EmptyPillar(Option<LetterCoord>),
+    NothingToUndo,
+    NothingToRedo,
+    InvalidNotation(String),
+    FileError(String, std::io::Error),
+    InvalidArgument(String),
+    OutOfPlate(LetterCoord),
+    UnsupportedDimensions(Dimensions),
+    UnsupportedPlayers,
+    NetworkError(std::io::Error),
+    Disconnected,
+    PlayerLeft(PlayerID),
+    ProtocolError(String),
+    EngineError(String),
+    Unsolved,
+    /// Le joueur a quitté la partie avant la fin
+    Stopped,
 }
 
 impl std::fmt::Display for P4Error {
@@ -20,6 +40,30 @@ impl std::fmt::Display for P4Error {
                 write!(f, "Error at pillar ??, the pillar is overfilled")
             }
             Self::EmptyPlayerPlayed => write!(f, "An Void tried to play"),
+            Self::InvalidMove(i) => write!(f, "{} is not a valid move", i),
+            Self::GameOver => write!(f, "The game is already over"),
+            Self::NoLegalMove => write!(f, "There is no legal move left"),
+            Self::EmptyPillar(Some(c)) => {
+                write!(f, "Error at pillar {:?}, the pillar is empty", c)
+            }
+            Self::EmptyPillar(None) => write!(f, "Error at pillar ??, the pillar is empty"),
+            Self::NothingToUndo => write!(f, "There is no move to undo"),
+            Self::NothingToRedo => write!(f, "There is no move to redo"),
+            Self::InvalidNotation(s) => write!(f, "{:?} is not a valid move notation", s),
+            Self::FileError(path, e) => write!(f, "Error while using the file {} : {}", path, e),
+            Self::InvalidArgument(s) => write!(f, "Invalid argument : {}", s),
+            Self::OutOfPlate(c) => write!(f, "Pillar {:?} is outside of the plate", c),
+            Self::UnsupportedDimensions(d) => {
+                write!(f, "This player cannot play on a {} plate", d)
+            }
+            Self::UnsupportedPlayers => write!(f, "This player only plays two-player games"),
+            Self::NetworkError(e) => write!(f, "Network error : {}", e),
+            Self::Disconnected => write!(f, "The other side closed the connection"),
+            Self::PlayerLeft(p) => write!(f, "Player {:?} left the game", p),
+            Self::ProtocolError(line) => write!(f, "Unexpected message {:?}", line),
+            Self::EngineError(s) => write!(f, "External engine error : {}", s),
+            Self::Unsolved => write!(f, "The position could not be solved in time"),
+            Self::Stopped => write!(f, "The game was left before its end"),
         }
     }
 }
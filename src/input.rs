@@ -1,4 +1,7 @@
-use crate::coord::{Coord, Letter, LetterCoord, LetterCoordBuilder, NumberCoord};
+use crate::{
+    coord::{Coord, Letter, LetterCoord, LetterCoordBuilder, NumberCoord},
+    dimensions::Dimensions,
+};
 
 #[derive(Clone, Default, PartialEq)]
 pub struct Input {
@@ -7,18 +10,9 @@ pub struct Input {
 }
 impl Coord for Input {
     fn to_xy_coord(&self) -> (u16, u16) {
-        let l = match self.letter.expect("need a letter to use") {
-            Letter::A => 0,
-            Letter::B => 1,
-            Letter::C => 2,
-            Letter::D => 3,
-        };
+        let l = self.letter.expect("need a letter to use") as u16;
         (l, self.number.expect("need a number to use") as u16)
     }
-    fn to_num(&self) -> u8 {
-        let (l, n) = self.to_xy_coord();
-        l as u8 + n as u8 * 4
-    }
 }
 
 impl Input {
@@ -76,7 +70,7 @@ impl std::fmt::Display for Input {
 
 impl From<(Letter, u8)> for Input {
     fn from(value: (Letter, u8)) -> Self {
-        debug_assert!(value.1 < 4);
+        debug_assert!(value.1 < Dimensions::MAX_SIDE);
         let mut res = Input::default();
         res.set_letter(value.0);
         res.set_number(value.1.into());
@@ -84,6 +78,13 @@ impl From<(Letter, u8)> for Input {
     }
 }
 
+impl From<(u8, u8)> for Input {
+    fn from((x, y): (u8, u8)) -> Self {
+        Input::from((Letter::from(x), y))
+    }
+}
+
+/// Sur le plateau standard
 impl From<u8> for Input {
     fn from(value: u8) -> Self {
         debug_assert!(value < 16);
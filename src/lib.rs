@@ -1,86 +1,263 @@
 //#![allow(unused_imports, dead_code, unused_variables)]
-use crate::{
+use crate::utils::{CloseTerminal, CreateTerminal, DrawSquare, DrawSquareAt, PrintAt};
+pub use crate::{
+    analysis::{Analysis, Eval, MoveReport, Verdict},
+    bitboard::Bitboard,
+    config::{Config, PlayerKind, ReplaySource, HELP},
+    coord::Letter,
+    dimensions::Dimensions,
+    engine::Engine,
     error::P4Error,
-    logic::{Human, Thinker},
-    plate::Plate,
-    player::PlayerID,
-    utils::{CloseTerminal, CreateTerminal, DrawSquare, DrawSquareAt, PrintAt},
+    input::Input,
+    logic::{Action, BotRng, RBot, Thinker},
+    mcts::{Budget, Mcts, Playout},
+    menu::{Difficulty, Menu, MenuEvent},
+    negamax::Negamax,
+    net::{
+        serve, Link, LocalThinker, Message, RemoteThinker, Spectator, DEFAULT_ADDRESS,
+        DEFAULT_SPECTATOR_ADDRESS,
+    },
+    notation::{parse_game_notation, parse_notation, to_game_notation, to_notation},
+    plate::{Line, Outcome, Plate, TypeOfDisplay},
+    player::{PlayerID, TurnOrder},
+    protocol::{EngineAdapter, EngineCommand, EngineReply, ProcessThinker},
+    replay::{moves_from_log, Replay},
+    solver::{BookEntry, OpeningBook, Solution, Solver, Value},
+    tactical::{Reason, Tactical},
+    tournament::{GameRecord, Standings, Tournament},
 };
 use crossterm::cursor::{RestorePosition, SavePosition};
 use crossterm::{execute, queue, style::Print};
-use input::Input;
 #[allow(unused_imports)]
 use log::{debug, error, info, trace, warn};
-use logic::RBot;
 use std::{
+    cell::RefCell,
+    fs,
     io::{self, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::atomic::{AtomicU16, Ordering},
     thread,
 };
 
+/// Colonne où commencent les messages, à droite du plateau affiché
+static PANEL_COLUMN: AtomicU16 = AtomicU16::new(15);
+
+/// Front-end terminal : dessine le plateau et interroge les joueurs,
+/// les règles sont déléguées à `Engine`.
 pub struct Game {
-    plateau: Plate,
-    next_player: PlayerID,
-    player_one: Box<dyn Thinker>,
-    player_two: Box<dyn Thinker>,
+    engine: Engine,
+    /// Dans l'ordre de jeu : `players[0]` joue `P1`
+    players: Vec<Box<dyn Thinker>>,
+    /// La partie y est réécrite après chaque coup
+    save_path: Option<PathBuf>,
 }
 
 impl Game {
-    fn get_player(&self) -> &dyn Thinker {
-        let res = match self.next_player {
-            PlayerID::P1 => self.player_one.as_ref().clone(),
-            PlayerID::P2 => self.player_two.as_ref().clone(),
+    const DEFAULT_SAVE_PATH: &'static str = "log/last_game.p4";
+    /// Une partie en réseau n'écrase pas la dernière partie locale
+    const NETWORK_SAVE_PATH: &'static str = "log/last_network_game.p4";
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), P4Error> {
+        let path = path.as_ref();
+        fs::write(path, self.engine.to_game_notation() + "\n")
+            .map_err(|e| P4Error::FileError(path.display().to_string(), e))
+    }
+
+    /// Relit la partie enregistrée dans `path`
+    pub fn read(path: impl AsRef<Path>) -> Result<Engine, P4Error> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .map_err(|e| P4Error::FileError(path.display().to_string(), e))?;
+        Engine::from_game_notation(&content)
+    }
+
+    /// Reprend la partie enregistrée dans `path`, les joueurs ne changent pas :
+    /// elle doit se jouer sur le même plateau et au même nombre
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<(), P4Error> {
+        let path = path.as_ref();
+        let engine = Game::read(path)?;
+        let (dims, players) = (engine.plate().dimensions(), engine.turn_order());
+        if dims != self.engine.plate().dimensions() || players != self.engine.turn_order() {
+            return Err(P4Error::InvalidArgument(format!(
+                "{} is a game of {} players on {}",
+                path.display(),
+                players.count(),
+                dims
+            )));
+        }
+        self.engine = engine;
+        info!("Game loaded from {}", path.display());
+        info!("{}{}", replay::LOG_LOADED, self.engine.to_notation());
+        Ok(())
+    }
+
+    fn autosave(&self) {
+        if let Some(path) = &self.save_path {
+            if let Err(e) = self.save(path) {
+                warn!("Autosave failed : {}", e);
+            }
+        }
+    }
+
+    fn get_player(&self, player: PlayerID) -> &dyn Thinker {
+        match player {
             PlayerID::Empty => unreachable!("An empty player cant exist (get_player)"),
-        };
-        res
-    }
-
-    fn collect_input(&self) -> Input {
-        let player = self.get_player();
-        Game::next_player_display(self.next_player).unwrap();
-        let intput = player.think(&self.plateau);
-        match intput {
-            Ok(i) => i,
-            Err(e) => panic!(
-                "Error when collecting an input from {} {}",
-                self.next_player, e
-            ),
+            p => self.players[p.index()].as_ref(),
+        }
+    }
+
+    /// La couleur du joueur, suivie de son nom s'il en a un
+    fn player_label(&self, player: PlayerID) -> String {
+        match self.get_player(player).name() {
+            Some(name) => format!("{} {}", player, name),
+            None => player.to_string(),
+        }
+    }
+
+    fn collect_input(&self) -> Result<Action, P4Error> {
+        let player = self.get_player(self.engine.current_player());
+        // un humain affiche lui-même son tour
+        if !player.is_human() {
+            Game::next_player_display(self.engine.current_player(), player.name())?;
         }
+        player
+            .act(self.engine.plate(), self.engine.history())
+            .map_err(|e| {
+                error!(
+                    "Error when collecting an input from {:?} : {}",
+                    self.engine.current_player(),
+                    e
+                );
+                e
+            })
     }
 
-    fn play_input(&mut self) {
-        let lc = self.collect_input();
-        self.plateau
-            .add_player(lc.clone(), self.next_player)
-            .expect("Error when playing input");
-        Self::log_placement_display(&self, lc.clone()).unwrap();
-        self.next_player.joue();
+    fn play_input(&mut self) -> Result<(), P4Error> {
+        let player = self.engine.current_player();
+        match self.collect_input()? {
+            Action::Play(lc) => {
+                self.engine.play(lc.clone())?;
+                info!("{}{}", replay::LOG_PLAYED, lc);
+                Self::log_placement_display(player, lc).unwrap();
+            }
+            Action::Undo => self.undo_turn(),
+            Action::Redo => self.redo_turn(),
+        }
+        self.autosave();
+        Ok(())
+    }
+
+    /// Nombre de coups à reprendre pour rendre la main au même humain :
+    /// on reprend aussi les réponses des bots qui l'ont suivi
+    fn moves_per_turn(&self) -> usize {
+        let order = self.engine.turn_order();
+        let current = self.engine.current_player();
+        let mut previous = order.previous(current);
+        let mut n = 1;
+        while previous != current && !self.get_player(previous).is_human() {
+            n += 1;
+            previous = order.previous(previous);
+        }
+        n
     }
 
-    fn win(&self) -> bool {
-        self.plateau.check_win()
+    fn undo_turn(&mut self) {
+        let n = self.moves_per_turn();
+        if self.engine.history().len() < n {
+            Self::history_display(self.engine.current_player(), "n'a rien à reprendre").unwrap();
+            return;
+        }
+        for _ in 0..n {
+            let mv = self.engine.undo().expect("Error when undoing a move");
+            info!("{}{}", replay::LOG_UNDONE, mv);
+        }
+        Self::history_display(self.engine.current_player(), "a repris son coup").unwrap();
+    }
+
+    fn redo_turn(&mut self) {
+        let n = self.moves_per_turn();
+        if self.engine.redo_count() < n {
+            Self::history_display(self.engine.current_player(), "n'a rien à rejouer").unwrap();
+            return;
+        }
+        let player = self.engine.current_player();
+        for _ in 0..n {
+            self.engine.redo().expect("Error when redoing a move");
+            if let Some(mv) = self.engine.history().last() {
+                info!("{}{}", replay::LOG_REDONE, mv);
+            }
+        }
+        Self::history_display(player, "a rejoué son coup").unwrap();
     }
 
-    fn run(&mut self) {
-        while !self.win() {
-            self.play_input();
-            self.plateau.plot();
+    fn run(&mut self) -> Result<(), P4Error> {
+        while self.engine.outcome() == Outcome::Ongoing {
+            self.play_input()?;
+            self.engine.plate().plot();
         }
         self.end();
+        Ok(())
     }
 
     fn end(&mut self) {
-        let s1 = String::from(format!("{} à perdu", self.next_player));
-        self.next_player.joue();
-        let s2 = String::from(format!("{} à Gagné", self.next_player));
-        let message = String::from(format!("{} {}", s1, s2));
+        let message = match self.engine.plate().winning_line() {
+            Some((winner, line)) => {
+                info!("Winning line of {:?} : {:?}", winner, line);
+                let losers: Vec<String> = self
+                    .engine
+                    .turn_order()
+                    .players()
+                    .filter(|p| *p != winner)
+                    .map(|p| self.player_label(p))
+                    .collect();
+                let verb = if losers.len() > 1 { "ont" } else { "à" };
+                format!(
+                    "{} {} perdu {} à Gagné",
+                    losers.join(", "),
+                    verb,
+                    self.player_label(winner)
+                )
+            }
+            None => String::from("Le cube est plein : match nul"),
+        };
         Self::message_display(message.as_str()).unwrap();
     }
+
+    /// Affiche l'analyse de la partie finie, et l'écrit à côté de la partie enregistrée
+    fn analyse(&self, depth: u8) {
+        println!("Analyse de la partie...");
+        let analysis = match Analysis::of_game(&self.engine, depth) {
+            Ok(analysis) => analysis,
+            Err(e) => {
+                warn!("Analysis failed : {}", e);
+                println!("Pas d'analyse : {}", e);
+                return;
+            }
+        };
+        print!("{}", analysis);
+        if let Some(path) = &self.save_path {
+            let path = Analysis::report_path(path);
+            match analysis.save(&path) {
+                Ok(()) => println!("Analyse enregistrée dans {}", path.display()),
+                Err(e) => warn!("The analysis was not saved : {}", e),
+            }
+        }
+    }
 }
 
 impl Game {
+    /// Les messages s'écartent des plateaux trop larges pour eux
+    fn place_panel(plt: &Plate) {
+        PANEL_COLUMN.store(plt.plot_width().max(12) + 3, Ordering::Relaxed);
+    }
+    fn panel((x, y): (u16, u16)) -> (u16, u16) {
+        (x.max(PANEL_COLUMN.load(Ordering::Relaxed)), y)
+    }
+
     const CURRENT_INPUT_DISPLAY_POSITION: (u16, u16) = (15, 6);
     fn input_display(input: Input) -> Result<(), P4Error> {
-        let (x, y) = Self::CURRENT_INPUT_DISPLAY_POSITION;
+        let (x, y) = Self::panel(Self::CURRENT_INPUT_DISPLAY_POSITION);
         queue!(
             io::stdout(),
             SavePosition,
@@ -92,7 +269,7 @@ impl Game {
     }
     const MESSAGE_DISPLAY_POSITION: (u16, u16) = (15, 8);
     fn message_display(message: &str) -> Result<(), P4Error> {
-        let (x, y) = Self::MESSAGE_DISPLAY_POSITION;
+        let (x, y) = Self::panel(Self::MESSAGE_DISPLAY_POSITION);
         queue!(
             io::stdout(),
             SavePosition,
@@ -104,26 +281,39 @@ impl Game {
     }
 
     const LOG_PLACEMENT_POSITION: (u16, u16) = (15, 1);
-    fn log_placement_display(&self, input: Input) -> Result<(), P4Error> {
-        let (x, y) = Self::LOG_PLACEMENT_POSITION;
+    fn log_placement_display(player: PlayerID, input: Input) -> Result<(), P4Error> {
+        let (x, y) = Self::panel(Self::LOG_PLACEMENT_POSITION);
         queue!(
             io::stdout(),
             SavePosition,
-            DrawSquareAt(x, y, self.next_player.color()),
-            Print(format!(" a joué en {}", input).as_str()),
+            DrawSquareAt(x, y, player.color()),
+            Print(format!(" {:<20}", format!("a joué en {}", input)).as_str()),
             RestorePosition
         )?;
         io::stdout().flush().unwrap();
         Ok(())
     }
+    fn history_display(player: PlayerID, message: &str) -> Result<(), P4Error> {
+        let (x, y) = Self::panel(Self::LOG_PLACEMENT_POSITION);
+        queue!(
+            io::stdout(),
+            SavePosition,
+            DrawSquareAt(x, y, player.color()),
+            Print(format!(" {:<20}", message).as_str()),
+            RestorePosition
+        )?;
+        io::stdout().flush()?;
+        Ok(())
+    }
     const NEXT_PLAYER_DISPLAY_POSITION: (u16, u16) = (15, 3);
-    fn next_player_display(next_player: PlayerID) -> Result<(), P4Error> {
-        let (x, y) = Self::NEXT_PLAYER_DISPLAY_POSITION;
+    fn next_player_display(next_player: PlayerID, name: Option<&str>) -> Result<(), P4Error> {
+        let (x, y) = Self::panel(Self::NEXT_PLAYER_DISPLAY_POSITION);
         queue!(
             io::stdout(),
             SavePosition,
             PrintAt(x, y, "Au tour de "),
             DrawSquare(next_player.color()),
+            Print(format!(" {:<20}", name.unwrap_or_default())),
             RestorePosition
         )?;
         io::stdout().flush()?;
@@ -131,14 +321,87 @@ impl Game {
     }
 }
 
-pub fn run() {
-    let plt = Plate::default();
+/// La graine des bots, notée dans le log pour pouvoir rejouer la partie avec `--seed`
+fn game_seed(config: &Config) -> u64 {
+    let seed = config.seed.unwrap_or_else(rand::random);
+    info!("{}{}", replay::LOG_SEED, seed);
+    seed
+}
+
+/// Demande le niveau des bots dans le menu de départ, et le retient pour la prochaine fois.
+/// `None` si le joueur quitte le menu
+fn choose_players(config: &Config) -> Result<Option<Vec<PlayerKind>>, P4Error> {
+    if !config.menu {
+        return Ok(Some(config.players.clone()));
+    }
+    let last = Difficulty::load(Difficulty::SAVE_PATH).unwrap_or_default();
+    let search = config.dimensions.is_standard() && config.turn_order.count() == 2;
+    let Some(level) = Menu::new(last, search).run()? else {
+        return Ok(None);
+    };
+    info!("Difficulty : {}", level);
+    if let Err(e) = level.save(Difficulty::SAVE_PATH) {
+        warn!("The difficulty will not be remembered : {}", e);
+    }
+    Ok(Some(
+        config
+            .players
+            .iter()
+            .map(|kind| match kind {
+                PlayerKind::Human => PlayerKind::Human,
+                _ => level.player_kind(),
+            })
+            .collect(),
+    ))
+}
+
+pub fn run(config: &Config) -> Result<(), P4Error> {
+    // une partie reprise garde son plateau et ses joueurs, qui doivent
+    // convenir aux bots avant d'ouvrir le terminal
+    let config = &match &config.resume {
+        Some(path) => {
+            let engine = Game::read(path)?;
+            let config = config.for_game(engine.plate().dimensions(), engine.turn_order());
+            config.check_duel()?;
+            config
+        }
+        None => config.clone(),
+    };
+    let Some(kinds) = choose_players(config)? else {
+        info!("No difficulty chosen");
+        return Ok(());
+    };
+    let config = &Config {
+        players: kinds,
+        menu: false,
+        ..config.clone()
+    };
+    config.check_duel()?;
+    let seed = game_seed(config);
+    // les couleurs suivent l'ordre de jeu : celui qui commence prend P1
+    let order = config.turn_order;
+    let count = order.count() as usize;
+    let players = order
+        .players()
+        .map(|color| {
+            let seat = (config.first.index() + color.index()) % count;
+            let seed = seed.wrapping_add(color.index() as u64);
+            config.players[seat].build(color, order, seed, config.names[seat].clone())
+        })
+        .collect::<Result<_, _>>()?;
     let mut game = Game {
-        plateau: plt,
-        next_player: PlayerID::P1,
-        player_one: Box::new(Human::new(PlayerID::P1)),
-        player_two: Box::new(RBot),
+        engine: Engine::with_players(config.dimensions, order),
+        players,
+        save_path: config.save.then(|| {
+            config
+                .resume
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(Game::DEFAULT_SAVE_PATH))
+        }),
     };
+    if let Some(path) = &config.resume {
+        game.load(path)?;
+    }
     //plateau.add_player(NumberCoord::new(1).expect("0").into(), player::PlayerID::P1).unwrap();
     //plateau.add_player(NumberCoord::new(2).expect("1"), player::PlayerID::P1).unwrap();
     //plateau.add_player(NumberCoord::new(3).expect("2"), player::PlayerID::P1).unwrap();
@@ -157,22 +420,153 @@ pub fn run() {
     // plateau.add_player(NumberCoord::new(9).expect("9"), player::Player::P2);
     // plateau.add_player(NumberCoord::new(9).expect("10"), player::Player::P2);
     // plateau.add_player(NumberCoord::new(15).expect("11"), player::Player::P1);
+    play_in_terminal(&mut game, config)
+}
+
+fn play_in_terminal(game: &mut Game, config: &Config) -> Result<(), P4Error> {
+    game.engine.plate().type_of_display.set(config.display);
+    Game::place_panel(game.engine.plate());
     execute!(io::stdout(), CreateTerminal).unwrap();
+    game.engine.plate().plot();
 
-    game.run();
+    let res = game.run();
 
-    thread::sleep(std::time::Duration::from_secs(3));
+    if res.is_ok() {
+        thread::sleep(std::time::Duration::from_secs(3));
+    }
     execute!(io::stdout(), CloseTerminal).unwrap();
+    if let (Ok(()), Some(depth)) = (&res, config.analysis) {
+        game.analyse(depth);
+    }
+    res
+}
+
+/// Rejoint une partie arbitrée par `p4_server` : la place et les règles
+/// viennent du serveur, le joueur local est celui de `--p1`
+pub fn join(config: &Config, address: &str) -> Result<(), P4Error> {
+    let link = Rc::new(RefCell::new(Link::connect(address)?));
+    let (seat, order, dims) = match link.borrow_mut().recv()? {
+        Message::Welcome {
+            seat,
+            players,
+            dims,
+        } => (seat, players, dims),
+        other => return Err(P4Error::ProtocolError(other.to_string())),
+    };
+    info!(
+        "Joined {} as {:?} ({} players on {})",
+        address,
+        seat,
+        order.count(),
+        dims
+    );
+    let seed = game_seed(config);
+    let players = order
+        .players()
+        .map(|p| -> Result<Box<dyn Thinker>, P4Error> {
+            if p == seat {
+                let local = config.players[0].build(p, order, seed, config.names[0].clone())?;
+                Ok(Box::new(LocalThinker::new(local, link.clone())))
+            } else {
+                Ok(Box::new(RemoteThinker::new(link.clone())))
+            }
+        })
+        .collect::<Result<_, _>>()?;
+    let mut game = Game {
+        engine: Engine::with_players(dims, order),
+        players,
+        save_path: config.save.then(|| PathBuf::from(Game::NETWORK_SAVE_PATH)),
+    };
+    play_in_terminal(&mut game, config)
+}
+
+/// Regarde une partie de `p4_server` sans y jouer : tous les coups
+/// viennent du serveur, Échap ou `q` referme le terminal
+pub fn watch(config: &Config, address: &str) -> Result<(), P4Error> {
+    let link = Rc::new(RefCell::new(Link::connect(address)?));
+    let (order, dims, moves) = match link.borrow_mut().recv()? {
+        Message::Watch {
+            players,
+            dims,
+            moves,
+        } => (players, dims, moves),
+        other => return Err(P4Error::ProtocolError(other.to_string())),
+    };
+    info!("Watching {} after {} moves", address, moves.len());
+    let mut engine = Engine::with_players(dims, order);
+    for mv in moves {
+        engine.play(mv)?;
+    }
+    let mut game = Game {
+        engine,
+        players: order
+            .players()
+            .map(|_| -> Box<dyn Thinker> { Box::new(Spectator::new(link.clone())) })
+            .collect(),
+        save_path: None,
+    };
+    match play_in_terminal(&mut game, config) {
+        Err(P4Error::Stopped) => {
+            info!("Stopped watching {}", address);
+            Ok(())
+        }
+        res => res,
+    }
+}
+
+/// Revoit une partie coup par coup dans le terminal
+pub fn replay(moves: Vec<Input>, config: &Config) -> Result<(), P4Error> {
+    let engine = Engine::with_players(config.dimensions, config.turn_order);
+    let mut replay = Replay::new(moves, engine)?;
+    Game::place_panel(replay.engine().plate());
+    execute!(io::stdout(), CreateTerminal)?;
+    let res = replay.run(config.display);
+    execute!(io::stdout(), CloseTerminal)?;
+    res
 }
 
+// Le code d'origine de coord, input, logic, pilier, plate, player et utils date
+// d'avant clippy : ses lints y sont tolérés plutôt que réécrits
+mod analysis;
+mod bitboard;
+mod config;
+#[allow(clippy::expect_fun_call)]
 mod coord;
+mod dimensions;
+mod engine;
+#[allow(clippy::unnecessary_cast, clippy::let_and_return)]
 mod input;
 
 mod error;
+#[allow(clippy::redundant_closure)]
 mod logic;
+mod mcts;
+mod menu;
+mod negamax;
+mod net;
+mod notation;
+#[allow(
+    clippy::redundant_closure,
+    clippy::into_iter_on_ref,
+    clippy::clone_on_copy
+)]
 mod pilier;
+#[allow(
+    clippy::unnecessary_cast,
+    clippy::redundant_closure,
+    clippy::extra_unused_lifetimes,
+    clippy::map_all_any_identity,
+    clippy::clone_on_copy
+)]
 mod plate;
+#[allow(clippy::derivable_impls)]
 mod player;
+mod protocol;
+mod replay;
+mod solver;
+mod tactical;
+mod tournament;
+#[allow(dead_code)]
 mod utils;
 
 pub fn add(left: usize, right: usize) -> usize {
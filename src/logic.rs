@@ -1,4 +1,4 @@
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 
 use crossterm::{
     event::{Event, KeyCode},
@@ -7,30 +7,82 @@ use crossterm::{
 
 use crate::plate::PreviewAction;
 use crate::{
-    coord::Letter, error::P4Error, input::Input, plate::Plate, player::PlayerID,
-    utils::CloseTerminal, Game,
+    coord::Letter,
+    error::P4Error,
+    input::Input,
+    negamax::Negamax,
+    plate::Plate,
+    player::{PlayerID, TurnOrder},
+    tactical::{Reason, Tactical},
+    utils::CloseTerminal,
+    Game,
 };
 
-use rand::Rng;
+use rand::{seq::SliceRandom, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Le générateur des joueurs aléatoires : contrairement à `StdRng`, sa suite
+/// pour une graine donnée ne change pas d'une version de `rand` à l'autre
+pub type BotRng = ChaCha8Rng;
+
+/// Ce qu'un joueur peut demander à son tour
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    Play(Input),
+    Undo,
+    Redo,
+}
 
 pub trait Thinker {
     fn think(&self, plt: &Plate) -> Result<Input, P4Error>;
+
+    /// Comme `think`, avec les coups qui ont mené à `plt` : seuls ceux qui
+    /// transmettent la partie, comme un moteur externe, en ont besoin
+    fn think_after(&self, plt: &Plate, _history: &[Input]) -> Result<Input, P4Error> {
+        self.think(plt)
+    }
+
+    /// Par défaut un `Thinker` ne fait que jouer
+    fn act(&self, plt: &Plate, history: &[Input]) -> Result<Action, P4Error> {
+        self.think_after(plt, history).map(Action::Play)
+    }
+
+    fn is_human(&self) -> bool {
+        false
+    }
+
+    fn name(&self) -> Option<&str> {
+        None
+    }
 }
 
-pub struct RBot;
+pub struct RBot {
+    rng: RefCell<BotRng>,
+}
+impl RBot {
+    pub fn new(seed: u64) -> RBot {
+        RBot::with_rng(BotRng::seed_from_u64(seed))
+    }
+
+    pub fn with_rng(rng: BotRng) -> RBot {
+        RBot {
+            rng: RefCell::new(rng),
+        }
+    }
+}
+impl Default for RBot {
+    fn default() -> Self {
+        RBot::new(rand::random())
+    }
+}
 impl Thinker for RBot {
+    /// Tire au hasard parmi les piliers qui ne sont pas pleins
     fn think(&self, plt: &Plate) -> Result<Input, P4Error> {
-        let mut rng = rand::thread_rng();
-        let random_number: u8 = rng.gen_range(0..=15);
-        let res: Input;
-        loop {
-            let input = Input::from(random_number);
-            if plt.playable(input.clone()) {
-                res = input;
-                break;
-            }
-        }
-        Ok(res)
+        let moves: Vec<Input> = plt.legal_moves().map(|(mv, _)| mv).collect();
+        moves
+            .choose(&mut *self.rng.borrow_mut())
+            .cloned()
+            .ok_or(P4Error::NoLegalMove)
     }
 }
 
@@ -54,19 +106,78 @@ impl Thinker for RBot {
 #[derive(Clone, Debug)]
 pub struct Human {
     pub player_id: PlayerID,
-    last_message_length : Cell<usize>,
+    name: Option<String>,
+    /// Pour les conseils, qui doivent connaître les adversaires
+    players: TurnOrder,
+    last_message_length: Cell<usize>,
 }
 impl Human {
-    pub fn new(player_id : PlayerID) -> Human {
-        Human {player_id, last_message_length: 0.into()}
+    /// Le temps de réflexion d'un conseil
+    const HINT_TIME: std::time::Duration = std::time::Duration::from_secs(1);
+
+    pub fn new(player_id: PlayerID) -> Human {
+        Human {
+            player_id,
+            name: None,
+            players: TurnOrder::default(),
+            last_message_length: 0.into(),
+        }
+    }
+    pub fn with_name(self, name: Option<String>) -> Human {
+        Human { name, ..self }
+    }
+    pub fn with_players(self, players: TurnOrder) -> Human {
+        Human { players, ..self }
+    }
+
+    /// `h` demande un conseil, sauf sur les plateaux qui ont une colonne H : `?` marche partout
+    fn is_hint_key(c: char, plt: &Plate) -> bool {
+        c == '?' || (c == 'h' && plt.dimensions().width() <= Letter::H as u8)
+    }
+
+    /// Le coup conseillé, avec ce qu'il apporte. La recherche alpha-beta le choisit
+    /// quand elle peut jouer, les règles du bot tactique sinon
+    pub fn hint(&self, plt: &Plate) -> Result<(Input, Reason), P4Error> {
+        let tactical = Tactical::new(self.player_id, self.players, 0);
+        if !plt.dimensions().is_standard() || self.players.count() > 2 {
+            return tactical.suggest(plt);
+        }
+        let mv = Negamax::new(self.player_id, 4, Self::HINT_TIME).think(plt)?;
+        let reason = tactical.explain(plt, &mv);
+        Ok((mv, reason))
+    }
+
+    fn message(&self, msg: &str) -> Result<(), P4Error> {
+        Game::message_display(" ".repeat(self.last_message_length.get()).as_str())?;
+        Game::message_display(msg)?;
+        self.last_message_length.set(msg.chars().count());
+        Ok(())
     }
 }
 impl Thinker for Human {
     fn think(&self, plt: &Plate) -> Result<Input, P4Error> {
+        loop {
+            if let Action::Play(input) = self.act(plt, &[])? {
+                return Ok(input);
+            }
+        }
+    }
+
+    fn is_human(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn act(&self, plt: &Plate, _history: &[Input]) -> Result<Action, P4Error> {
         plt.plot();
+        Game::next_player_display(self.player_id, self.name())?;
         let mut res = Input::default();
         let mut prec_prev = res.clone();
-        
+        let mut hinted: Option<Input> = None;
+
         Game::input_display(res.clone())?;
         crossterm::terminal::enable_raw_mode().unwrap();
         loop {
@@ -82,37 +193,62 @@ impl Thinker for Human {
             };
             let keycode = key_ev.code;
 
+            // le conseil s'efface à la touche suivante
+            if let Some(hint) = hinted.take() {
+                plt.preview(hint.clone(), PreviewAction::Remove)?;
+                if prec_prev.is_valid() && prec_prev == hint {
+                    plt.preview(hint, PreviewAction::Add)?;
+                }
+            }
+
             match keycode {
-                KeyCode::Char('A') | KeyCode::Char('a') => res.set_letter(Letter::A),
-                KeyCode::Char('B') | KeyCode::Char('b') => res.set_letter(Letter::B),
-                KeyCode::Char('C') | KeyCode::Char('c') => res.set_letter(Letter::C),
-                KeyCode::Char('D') | KeyCode::Char('d') => res.set_letter(Letter::D),
-
-                KeyCode::Char('0') => res.set_number(0),
-                KeyCode::Char('1') => res.set_number(1),
-                KeyCode::Char('2') => res.set_number(2),
-                KeyCode::Char('3') => res.set_number(3),
+                KeyCode::Char(c) if c.is_ascii_digit() => {
+                    res.set_number(c.to_digit(10).unwrap_or_default() as usize)
+                }
                 KeyCode::Char('s') => {
                     plt.switch_view();
                     plt.clear_plot();
                     plt.plot()
                 }
+                KeyCode::Char('u') => {
+                    crossterm::terminal::disable_raw_mode().unwrap();
+                    return Ok(Action::Undo);
+                }
+                KeyCode::Char('r') => {
+                    crossterm::terminal::disable_raw_mode().unwrap();
+                    return Ok(Action::Redo);
+                }
+                KeyCode::Char(c) if Self::is_hint_key(c, plt) => {
+                    self.message("Je réfléchis...")?;
+                    match self.hint(plt) {
+                        Ok((hint, reason)) => {
+                            plt.preview(hint.clone(), PreviewAction::Hint)?;
+                            self.message(&format!("Conseil : {}, {}", hint, reason))?;
+                            hinted = Some(hint);
+                        }
+                        Err(e) => self.message(&format!("Pas de conseil : {}", e))?,
+                    }
+                    continue;
+                }
                 KeyCode::Enter => {
                     if res.is_valid() {
-                        if plt.playable(res.clone()) {
-                            break; 
+                        if !plt.contains(&res) {
+                            let msg = "Cette colonne n'est pas sur le plateau";
+                            Game::message_display(msg)?;
+                            self.last_message_length.set(msg.len());
+                            continue;
+                        } else if plt.playable(res.clone()) {
+                            break;
                         } else {
                             let msg = "La colonne choisie est deja pleine";
                             Game::message_display(msg)?;
-                            self.last_message_length.set(msg.len()); 
+                            self.last_message_length.set(msg.len());
                             continue;
                         }
-
-                    }
-                    else { 
+                    } else {
                         let msg = "Tu dois entrer un input corect";
                         Game::message_display(msg)?;
-                        self.last_message_length.set(msg.len()); 
+                        self.last_message_length.set(msg.len());
                         continue;
                     }
                 }
@@ -122,10 +258,17 @@ impl Thinker for Human {
                     disable_raw_mode().expect("j'ai pas reussi frerre");
                     std::process::exit(0);
                 }
+                KeyCode::Char(c) => {
+                    if let Some(letter) = Letter::from_char(c) {
+                        res.set_letter(letter)
+                    }
+                }
                 _ => {}
             }
             Game::input_display(res.clone())?;
-            if !(res.is_valid() && plt.playable(res.clone())) { continue; }
+            if !(res.is_valid() && plt.playable(res.clone())) {
+                continue;
+            }
             plt.preview(res.clone(), PreviewAction::Add)?;
             if prec_prev.is_valid() && (res != prec_prev) {
                 plt.preview(prec_prev.clone(), PreviewAction::Remove)
@@ -133,11 +276,123 @@ impl Thinker for Human {
             }
             prec_prev = res.clone();
             Game::message_display(" ".repeat(self.last_message_length.get()).as_str())?;
-            self.last_message_length.set(0); 
+            self.last_message_length.set(0);
         }
 
         crossterm::terminal::disable_raw_mode().unwrap();
 
-        Ok(res)
+        Ok(Action::Play(res))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dimensions::Dimensions, engine::Engine, plate::Outcome, player::TurnOrder};
+
+    fn choices(bot: &RBot, n: usize) -> String {
+        let plt = Plate::default();
+        (0..n)
+            .map(|_| bot.think(&plt).unwrap().to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Joue une partie entière entre bots aléatoires
+    fn random_game(dims: Dimensions, players: TurnOrder, seed: u64) -> Engine {
+        let bots: Vec<RBot> = players
+            .players()
+            .map(|p| RBot::new(seed.wrapping_add(p.index() as u64)))
+            .collect();
+        let mut engine = Engine::with_players(dims, players);
+        while engine.outcome() == Outcome::Ongoing {
+            let mv = engine.ask(&bots[engine.current_player().index()]).unwrap();
+            engine.play(mv).unwrap();
+        }
+        engine
+    }
+
+    #[test]
+    fn random_games_run_to_completion() {
+        let cases = [
+            (Dimensions::STANDARD, TurnOrder::default(), 2000),
+            ("5x5x5:4".parse().unwrap(), TurnOrder::new(3).unwrap(), 300),
+            ("3x3x2:3".parse().unwrap(), TurnOrder::new(4).unwrap(), 500),
+        ];
+        let mut draws = 0;
+        for (dims, players, games) in cases {
+            let cells = dims.pillars() * dims.height() as usize;
+            for seed in 0..games {
+                let engine = random_game(dims, players, seed);
+                match engine.outcome() {
+                    // le gagnant est le dernier à avoir joué
+                    Outcome::Win(p, _) => assert_eq!(p, engine.current_player()),
+                    Outcome::Draw => {
+                        assert_eq!(engine.history().len(), cells);
+                        draws += 1;
+                    }
+                    Outcome::Ongoing => unreachable!(),
+                }
+                assert!(engine.history().len() <= cells);
+            }
+        }
+        // sur le petit plateau, certaines parties remplissent tout
+        assert!(draws > 0);
+    }
+
+    #[test]
+    fn full_plate_has_no_move() {
+        let dims = Dimensions::new(2, 1, 1, 2).unwrap();
+        let mut plt = Plate::new(dims);
+        plt.add_player(Input::from((0, 0)), PlayerID::P1).unwrap();
+        plt.add_player(Input::from((1, 0)), PlayerID::P2).unwrap();
+        assert!(matches!(
+            RBot::new(0).think(&plt),
+            Err(P4Error::NoLegalMove)
+        ));
+        // le seul pilier libre est toujours choisi
+        plt.undo(Input::from((1, 0))).unwrap();
+        for seed in 0..50 {
+            assert_eq!(RBot::new(seed).think(&plt).unwrap(), Input::from((1, 0)));
+        }
+    }
+
+    #[test]
+    fn hints_are_explained() {
+        let engine = Engine::from_notation("A0 A1 B0 B1 C0").unwrap();
+        let (mv, reason) = Human::new(PlayerID::P2).hint(engine.plate()).unwrap();
+        assert_eq!(mv, Input::from((3, 0)));
+        assert_eq!(
+            reason,
+            Reason::Blocks {
+                player: PlayerID::P1,
+                tokens: 3
+            }
+        );
+        // à trois, sans recherche alpha-beta, les règles du bot tactique suffisent
+        let players = TurnOrder::new(3).unwrap();
+        let mut engine = Engine::with_players("5x5x5:4".parse().unwrap(), players);
+        engine.play_notation("A0 E4 E4 B0 E3 E3 C0").unwrap();
+        let human = Human::new(PlayerID::P2).with_players(players);
+        assert!(matches!(
+            human.hint(engine.plate()).unwrap().1,
+            Reason::Blocks { .. }
+        ));
+        // sur 5 colonnes, h demande un conseil
+        assert!(Human::is_hint_key('h', engine.plate()));
+        // sur un plateau de 8 colonnes, h est la colonne H
+        let wide = Plate::new("8x2x2:2".parse().unwrap());
+        assert!(!Human::is_hint_key('h', &wide));
+        assert!(Human::is_hint_key('?', &wide));
+    }
+
+    #[test]
+    fn same_seed_same_moves() {
+        assert_eq!(choices(&RBot::new(42), 20), choices(&RBot::new(42), 20));
+        assert_ne!(choices(&RBot::new(42), 20), choices(&RBot::new(43), 20));
+        assert_eq!(
+            choices(&RBot::with_rng(BotRng::seed_from_u64(7)), 20),
+            choices(&RBot::new(7), 20)
+        );
     }
 }
@@ -1,16 +1,70 @@
 #![allow(unused_imports, dead_code)]
 use log::{debug, error, info, trace, warn};
-use log4rs;
-use puissance::run;
+use puissance::{
+    join, moves_from_log, parse_game_notation, replay, run, watch, Config, P4Error, ReplaySource,
+};
+use std::{fs, path::Path};
+
+fn read(path: &Path) -> Result<String, P4Error> {
+    fs::read_to_string(path).map_err(|e| P4Error::FileError(path.display().to_string(), e))
+}
 
 fn main() {
-    if let Err(e) = log4rs::init_file("logger_config.yaml", Default::default()) {
-        panic!("{}", e);
+    let config = match Config::from_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, puissance::HELP);
+            std::process::exit(2);
+        }
+    };
+    if config.help {
+        println!("{}", puissance::HELP);
+        return;
+    }
+
+    // lu avant d'initialiser le logger, qui vide `log/my.log`
+    let to_replay = config.replay.as_ref().map(|source| match source {
+        // une partie enregistrée se rejoue sur son plateau et avec ses joueurs
+        ReplaySource::Save(path) => read(path)
+            .and_then(|s| parse_game_notation(&s))
+            .map(|(dims, players, moves)| (config.for_game(dims, players), moves)),
+        ReplaySource::Log(path) => read(path)
+            .and_then(|s| moves_from_log(&s))
+            .map(|moves| (config.clone(), moves)),
+    });
+
+    if let Err(e) = log4rs::init_file(&config.logger_config, Default::default()) {
+        // un mauvais `--log-config` est une erreur d'argument comme les autres
+        let e = P4Error::InvalidArgument(format!(
+            "cannot set the logger from {} : {}",
+            config.logger_config.display(),
+            e
+        ));
+        eprintln!("{}\n\n{}", e, puissance::HELP);
+        std::process::exit(2);
     }
     info!("Logger succesfully set\n\tStarting program . . .");
 
-    info!("Begin of the game");
-    run();
-    info!("End of the game");
+    let res = match to_replay {
+        Some(game) => {
+            info!("Replaying a game");
+            game.and_then(|(config, moves)| replay(moves, &config))
+        }
+        None => {
+            info!("Begin of the game");
+            let res = match (&config.connect, &config.watch) {
+                (Some(address), _) => join(&config, address),
+                (_, Some(address)) => watch(&config, address),
+                _ => run(&config),
+            };
+            info!("End of the game");
+            res
+        }
+    };
+    if let Err(e) = res {
+        error!("{}", e);
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
     info!("End of program");
 }
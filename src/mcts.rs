@@ -0,0 +1,362 @@
+//! Recherche arborescente Monte-Carlo (UCT) : l'arbre grandit d'un nœud par
+//! itération, chaque nouveau nœud est évalué par une partie jouée jusqu'au bout.
+//! Elle ne demande aucune évaluation des positions, et joue donc sur tous les
+//! plateaux et à tous les nombres de joueurs.
+use std::{
+    cell::RefCell,
+    time::{Duration, Instant},
+};
+
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    error::P4Error,
+    input::Input,
+    logic::{BotRng, Thinker},
+    plate::Plate,
+    player::{PlayerID, TurnOrder},
+};
+
+/// Quand arrêter la recherche
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Budget {
+    Iterations(u32),
+    Time(Duration),
+}
+
+/// Comment finir les parties depuis un nouveau nœud
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Playout {
+    /// Coups au hasard
+    Random,
+    /// Gagne ou bloque quand c'est possible en un coup, au hasard sinon
+    Light,
+}
+
+impl std::fmt::Display for Budget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Budget::Iterations(n) => write!(f, "{}", n),
+            Budget::Time(d) => write!(f, "{}ms", d.as_millis()),
+        }
+    }
+}
+
+impl std::fmt::Display for Playout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Playout::Random => write!(f, "random"),
+            Playout::Light => write!(f, "light"),
+        }
+    }
+}
+
+struct Node {
+    mv: Option<Input>,
+    /// Le joueur qui a joué `mv`
+    mover: PlayerID,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<Input>,
+    /// La partie est finie après `mv`, gagnée par `winner` ou nulle
+    terminal: bool,
+    winner: Option<PlayerID>,
+    visits: u32,
+    /// Les points de `mover` : 1 par victoire, 1/2 par nul
+    reward: f64,
+}
+
+impl Node {
+    fn new(mv: Option<Input>, mover: PlayerID, parent: Option<usize>, plt: &Plate) -> Node {
+        Node {
+            mv,
+            mover,
+            parent,
+            children: Vec::new(),
+            untried: plt.legal_moves().map(|(mv, _)| mv).collect(),
+            terminal: false,
+            winner: None,
+            visits: 0,
+            reward: 0.,
+        }
+    }
+
+    fn end(self, winner: Option<PlayerID>) -> Node {
+        Node {
+            terminal: true,
+            winner,
+            untried: Vec::new(),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Mcts {
+    pub player_id: PlayerID,
+    players: TurnOrder,
+    budget: Budget,
+    playout: Playout,
+    /// La constante `c` d'UCT : plus elle est grande, plus les coups peu essayés sont explorés
+    exploration: f64,
+    rng: RefCell<BotRng>,
+}
+
+impl Mcts {
+    pub fn new(
+        player_id: PlayerID,
+        players: TurnOrder,
+        budget: Budget,
+        playout: Playout,
+        seed: u64,
+    ) -> Mcts {
+        Mcts {
+            player_id,
+            players,
+            budget,
+            playout,
+            exploration: std::f64::consts::SQRT_2,
+            rng: RefCell::new(BotRng::seed_from_u64(seed)),
+        }
+    }
+
+    pub fn with_exploration(self, exploration: f64) -> Mcts {
+        Mcts {
+            exploration,
+            ..self
+        }
+    }
+
+    /// L'enfant qui maximise la borne UCT, tous ont déjà été visités
+    fn select(&self, tree: &[Node], node: usize) -> usize {
+        let log_visits = (tree[node].visits as f64).ln();
+        let uct = |child: &usize| {
+            let c = &tree[*child];
+            c.reward / c.visits as f64 + self.exploration * (log_visits / c.visits as f64).sqrt()
+        };
+        *tree[node]
+            .children
+            .iter()
+            .max_by(|a, b| uct(a).total_cmp(&uct(b)))
+            .expect("Only nodes with children are selected")
+    }
+
+    fn playout_move(&self, plt: &Plate, mover: PlayerID, rng: &mut BotRng) -> Option<Input> {
+        let moves: Vec<Input> = plt.legal_moves().map(|(mv, _)| mv).collect();
+        let random = moves.choose(rng)?;
+        let chosen = match self.playout {
+            Playout::Random => random,
+            Playout::Light => {
+                let next = self.players.next(mover);
+                moves
+                    .iter()
+                    .find(|mv| plt.completes_line(*mv, mover))
+                    .or_else(|| moves.iter().find(|mv| plt.completes_line(*mv, next)))
+                    .unwrap_or(random)
+            }
+        };
+        Some(chosen.clone())
+    }
+
+    /// Finit la partie, les coups joués sont ajoutés à `played`. Renvoie le gagnant
+    fn simulate(
+        &self,
+        plt: &mut Plate,
+        mut mover: PlayerID,
+        played: &mut Vec<Input>,
+        rng: &mut BotRng,
+    ) -> Result<Option<PlayerID>, P4Error> {
+        while let Some(mv) = self.playout_move(plt, mover, rng) {
+            let wins = plt.completes_line(&mv, mover);
+            plt.add_player(mv.clone(), mover)?;
+            played.push(mv);
+            if wins {
+                return Ok(Some(mover));
+            }
+            mover = self.players.next(mover);
+        }
+        Ok(None)
+    }
+
+    /// Une itération : sélection, expansion, simulation puis rétropropagation
+    fn iterate(
+        &self,
+        tree: &mut Vec<Node>,
+        plt: &mut Plate,
+        rng: &mut BotRng,
+    ) -> Result<(), P4Error> {
+        let mut node = 0;
+        let mut mover = self.player_id;
+        let mut played = Vec::new();
+        while tree[node].untried.is_empty() && !tree[node].children.is_empty() {
+            node = self.select(tree, node);
+            let mv = tree[node].mv.clone().expect("Only the root has no move");
+            plt.add_player(mv.clone(), mover)?;
+            played.push(mv);
+            mover = self.players.next(mover);
+        }
+        if !tree[node].untried.is_empty() {
+            let untried = &mut tree[node].untried;
+            let mv = untried.swap_remove(rng.gen_range(0..untried.len()));
+            let wins = plt.completes_line(&mv, mover);
+            plt.add_player(mv.clone(), mover)?;
+            played.push(mv.clone());
+            let child = Node::new(Some(mv), mover, Some(node), plt);
+            let child = if wins {
+                child.end(Some(mover))
+            } else if plt.is_full() {
+                child.end(None)
+            } else {
+                child
+            };
+            tree.push(child);
+            let id = tree.len() - 1;
+            tree[node].children.push(id);
+            node = id;
+            mover = self.players.next(mover);
+        }
+        let winner = match tree[node].terminal {
+            true => tree[node].winner,
+            false => self.simulate(plt, mover, &mut played, rng)?,
+        };
+        let mut current = Some(node);
+        while let Some(i) = current {
+            let n = &mut tree[i];
+            n.visits += 1;
+            n.reward += match winner {
+                Some(p) if p == n.mover => 1.,
+                Some(_) => 0.,
+                None => 0.5,
+            };
+            current = n.parent;
+        }
+        for mv in played.into_iter().rev() {
+            plt.undo(mv)?;
+        }
+        Ok(())
+    }
+}
+
+impl Thinker for Mcts {
+    fn think(&self, plt: &Plate) -> Result<Input, P4Error> {
+        let start = Instant::now();
+        let mut work = plt.clone();
+        let mut rng = self.rng.borrow_mut();
+        let mut tree = vec![Node::new(None, PlayerID::Empty, None, plt)];
+        if tree[0].untried.is_empty() {
+            return Err(P4Error::NoLegalMove);
+        }
+        let mut iterations = 0;
+        loop {
+            self.iterate(&mut tree, &mut work, &mut rng)?;
+            iterations += 1;
+            let done = match self.budget {
+                Budget::Iterations(n) => iterations >= n,
+                Budget::Time(budget) => start.elapsed() >= budget,
+            };
+            if done {
+                break;
+            }
+        }
+
+        let mut children: Vec<&Node> = tree[0].children.iter().map(|c| &tree[*c]).collect();
+        children.sort_by_key(|c| std::cmp::Reverse(c.visits));
+        let stats: Vec<String> = children
+            .iter()
+            .map(|c| {
+                format!(
+                    "{} {}/{:.0}%",
+                    c.mv.as_ref().expect("Children have a move"),
+                    c.visits,
+                    100. * c.reward / c.visits as f64
+                )
+            })
+            .collect();
+        log::info!(
+            "MCTS {} iterations in {} ms, visits/score : {}",
+            iterations,
+            start.elapsed().as_millis(),
+            stats.join(", ")
+        );
+        children[0].mv.clone().ok_or(P4Error::NoLegalMove)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dimensions::Dimensions, engine::Engine, logic::RBot, plate::Outcome};
+
+    fn bot(player_id: PlayerID, playout: Playout, iterations: u32) -> Mcts {
+        Mcts::new(
+            player_id,
+            TurnOrder::default(),
+            Budget::Iterations(iterations),
+            playout,
+            0,
+        )
+    }
+
+    #[test]
+    fn takes_a_win_and_blocks_a_threat() {
+        for playout in [Playout::Random, Playout::Light] {
+            // P1 gagne en D0
+            let engine = Engine::from_notation("A0 A1 B0 B1 C0 C1").unwrap();
+            let mv = bot(PlayerID::P1, playout, 500)
+                .think(engine.plate())
+                .unwrap();
+            assert_eq!(mv.to_string(), "D0");
+            // P2 doit bloquer D0
+            let engine = Engine::from_notation("A0 A1 B0 B2 C0").unwrap();
+            let mv = bot(PlayerID::P2, playout, 500)
+                .think(engine.plate())
+                .unwrap();
+            assert_eq!(mv.to_string(), "D0");
+        }
+    }
+
+    #[test]
+    fn beats_random_play() {
+        let mut wins = 0;
+        for seed in 0..4 {
+            let mcts = bot(PlayerID::P1, Playout::Light, 200).with_exploration(1.);
+            let random = RBot::new(seed);
+            let mut engine = Engine::new();
+            while engine.outcome() == Outcome::Ongoing {
+                let mv = match engine.current_player() {
+                    PlayerID::P1 => engine.ask(&mcts),
+                    _ => engine.ask(&random),
+                };
+                engine.play(mv.unwrap()).unwrap();
+            }
+            if let Outcome::Win(PlayerID::P1, _) = engine.outcome() {
+                wins += 1;
+            }
+        }
+        assert!(wins >= 3, "{} wins out of 4", wins);
+    }
+
+    #[test]
+    fn plays_on_any_plate() {
+        let dims: Dimensions = "3x3x2:3".parse().unwrap();
+        let players = TurnOrder::new(3).unwrap();
+        let mut engine = Engine::with_players(dims, players);
+        let bots: Vec<Mcts> = players
+            .players()
+            .map(|p| {
+                Mcts::new(
+                    p,
+                    players,
+                    Budget::Time(Duration::from_millis(5)),
+                    Playout::Random,
+                    1,
+                )
+            })
+            .collect();
+        while engine.outcome() == Outcome::Ongoing {
+            let mv = engine.ask(&bots[engine.current_player().index()]).unwrap();
+            engine.play(mv).unwrap();
+        }
+        assert!(engine.history().len() <= 18);
+    }
+}
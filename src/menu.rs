@@ -0,0 +1,304 @@
+//! Le menu de départ : le niveau des bots, retenu d'une partie à l'autre.
+use std::{
+    fs,
+    io::{self, Write},
+    ops::RangeInclusive,
+    path::Path,
+    str::FromStr,
+    time::Duration,
+};
+
+use crossterm::{
+    event::{Event, KeyCode},
+    execute, queue,
+    terminal::{self, ClearType},
+};
+
+use crate::{
+    config::PlayerKind,
+    error::P4Error,
+    mcts::{Budget, Playout},
+    utils::{CloseTerminal, CreateTerminal, PrintAt},
+};
+
+/// Le niveau des bots
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Difficulty {
+    Random,
+    Tactical,
+    /// Une recherche alpha-beta de `depth` demi-coups
+    Depth(u8),
+    /// Une recherche Monte-Carlo pendant le temps donné
+    Timed(Duration),
+}
+
+impl Difficulty {
+    pub const DEPTHS: RangeInclusive<u8> = 1..=8;
+    pub const SECONDS: RangeInclusive<u64> = 1..=30;
+    /// Où le dernier niveau choisi est retenu
+    pub const SAVE_PATH: &'static str = "log/difficulty";
+
+    pub fn player_kind(&self) -> PlayerKind {
+        match self {
+            Difficulty::Random => PlayerKind::Random,
+            Difficulty::Tactical => PlayerKind::Tactical,
+            Difficulty::Depth(depth) => PlayerKind::Negamax {
+                depth: *depth,
+                time_budget: Duration::from_secs(60),
+            },
+            Difficulty::Timed(budget) => PlayerKind::Mcts {
+                budget: Budget::Time(*budget),
+                playout: Playout::Light,
+            },
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            Difficulty::Random => String::from("Hasard"),
+            Difficulty::Tactical => String::from("Tactique"),
+            Difficulty::Depth(depth) => format!("Recherche à < {} > coups", depth),
+            Difficulty::Timed(budget) => format!("Réflexion de < {} > s", budget.as_secs()),
+        }
+    }
+
+    /// Le niveau voisin, pour ceux qui se règlent
+    fn adjust(&self, step: i8) -> Difficulty {
+        let clamp = |n: i64, range: (i64, i64)| n.clamp(range.0, range.1);
+        match self {
+            Difficulty::Depth(depth) => {
+                let range = (*Self::DEPTHS.start() as i64, *Self::DEPTHS.end() as i64);
+                Difficulty::Depth(clamp(*depth as i64 + step as i64, range) as u8)
+            }
+            Difficulty::Timed(budget) => {
+                let range = (*Self::SECONDS.start() as i64, *Self::SECONDS.end() as i64);
+                let secs = clamp(budget.as_secs() as i64 + step as i64, range);
+                Difficulty::Timed(Duration::from_secs(secs as u64))
+            }
+            other => *other,
+        }
+    }
+
+    /// Le dernier niveau choisi, `None` s'il n'y en a pas encore
+    pub fn load(path: impl AsRef<Path>) -> Option<Difficulty> {
+        let content = fs::read_to_string(path.as_ref()).ok()?;
+        match content.trim().parse() {
+            Ok(level) => Some(level),
+            Err(e) => {
+                log::warn!("Ignoring the saved difficulty : {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), P4Error> {
+        let path = path.as_ref();
+        fs::write(path, format!("{}\n", self))
+            .map_err(|e| P4Error::FileError(path.display().to_string(), e))
+    }
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Difficulty::Depth(4)
+    }
+}
+
+impl std::fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Difficulty::Random => write!(f, "random"),
+            Difficulty::Tactical => write!(f, "tactical"),
+            Difficulty::Depth(depth) => write!(f, "depth:{}", depth),
+            Difficulty::Timed(budget) => write!(f, "time:{}", budget.as_secs()),
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = P4Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || P4Error::InvalidArgument(format!("unknown difficulty {}", s));
+        let level = match s.split_once(':') {
+            None if s == "random" => Difficulty::Random,
+            None if s == "tactical" => Difficulty::Tactical,
+            Some(("depth", n)) => Difficulty::Depth(n.parse().map_err(|_| err())?),
+            Some(("time", n)) => {
+                Difficulty::Timed(Duration::from_secs(n.parse().map_err(|_| err())?))
+            }
+            _ => return Err(err()),
+        };
+        // un niveau hors des bornes du menu n'a pas pu y être choisi
+        if level.adjust(0) != level {
+            return Err(err());
+        }
+        Ok(level)
+    }
+}
+
+/// Ce que devient le menu après une touche
+#[derive(Clone, Debug, PartialEq)]
+pub enum MenuEvent {
+    Continue,
+    Chosen(Difficulty),
+    Quit,
+}
+
+pub struct Menu {
+    levels: Vec<Difficulty>,
+    selected: usize,
+}
+
+impl Menu {
+    const TITLE: &'static str = "Niveau des bots";
+    const KEYS: &'static str =
+        "↑ ↓ pour choisir, ← → pour régler, Entrée pour jouer, Échap pour quitter";
+
+    /// `search` : la recherche alpha-beta est-elle possible sur ce plateau ?
+    pub fn new(last: Difficulty, search: bool) -> Menu {
+        let mut levels = vec![Difficulty::Random, Difficulty::Tactical];
+        if search {
+            levels.push(Difficulty::default());
+        }
+        levels.push(Difficulty::Timed(Duration::from_secs(2)));
+        // le dernier choix reprend sa ligne, avec son réglage
+        let selected = levels
+            .iter()
+            .position(|l| std::mem::discriminant(l) == std::mem::discriminant(&last));
+        if let Some(i) = selected {
+            levels[i] = last;
+        }
+        Menu {
+            levels,
+            selected: selected.unwrap_or(1),
+        }
+    }
+
+    pub fn selected(&self) -> Difficulty {
+        self.levels[self.selected]
+    }
+
+    pub fn handle(&mut self, key: KeyCode) -> MenuEvent {
+        match key {
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(self.levels.len() - 1),
+            KeyCode::Left => self.levels[self.selected] = self.selected().adjust(-1),
+            KeyCode::Right => self.levels[self.selected] = self.selected().adjust(1),
+            KeyCode::Char(c) => match c.to_digit(10) {
+                Some(n) if (1..=self.levels.len() as u32).contains(&n) => {
+                    self.selected = n as usize - 1
+                }
+                _ => {}
+            },
+            KeyCode::Enter => return MenuEvent::Chosen(self.selected()),
+            KeyCode::Esc => return MenuEvent::Quit,
+            _ => {}
+        }
+        MenuEvent::Continue
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            String::from(Self::TITLE),
+            String::from(Self::KEYS),
+            String::new(),
+        ];
+        lines.extend(self.levels.iter().enumerate().map(|(i, level)| {
+            let marker = if i == self.selected { '>' } else { ' ' };
+            format!("{} {}. {}", marker, i + 1, level.label())
+        }));
+        lines
+    }
+
+    fn plot(&self) -> Result<(), P4Error> {
+        queue!(io::stdout(), terminal::Clear(ClearType::All))?;
+        for (y, line) in self.lines().into_iter().enumerate() {
+            queue!(io::stdout(), PrintAt(2, y as u16 + 1, line))?;
+        }
+        io::stdout().flush()?;
+        Ok(())
+    }
+
+    /// Affiche le menu jusqu'au choix d'un niveau, `None` si le joueur quitte
+    pub fn run(&mut self) -> Result<Option<Difficulty>, P4Error> {
+        execute!(io::stdout(), CreateTerminal)?;
+        terminal::enable_raw_mode()?;
+        let res = loop {
+            if let Err(e) = self.plot() {
+                break Err(e);
+            }
+            let key = match crossterm::event::read() {
+                Ok(Event::Key(k)) => k.code,
+                Ok(_) => continue,
+                Err(e) => break Err(P4Error::OutputInterfaceError(e)),
+            };
+            match self.handle(key) {
+                MenuEvent::Continue => {}
+                MenuEvent::Chosen(level) => break Ok(Some(level)),
+                MenuEvent::Quit => break Ok(None),
+            }
+        };
+        execute!(io::stdout(), CloseTerminal)?;
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_round_trip() {
+        for level in [
+            Difficulty::Random,
+            Difficulty::Tactical,
+            Difficulty::Depth(6),
+            Difficulty::Timed(Duration::from_secs(5)),
+        ] {
+            assert_eq!(level.to_string().parse::<Difficulty>().unwrap(), level);
+            let kind = level.player_kind();
+            assert_eq!(kind.to_string().parse::<PlayerKind>().unwrap(), kind);
+        }
+        for bad in ["hard", "depth:0", "depth:x", "time:31", "random:2"] {
+            assert!(bad.parse::<Difficulty>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn last_choice_is_remembered() {
+        let path = std::env::temp_dir().join(format!("p4_difficulty_{}", std::process::id()));
+        assert_eq!(Difficulty::load(&path), None);
+        Difficulty::Depth(6).save(&path).unwrap();
+        let last = Difficulty::load(&path).unwrap();
+        let menu = Menu::new(last, true);
+        assert_eq!(menu.selected(), Difficulty::Depth(6));
+        assert!(menu.lines()[5].starts_with("> 3. Recherche à < 6 >"));
+        fs::write(&path, "nonsense").unwrap();
+        assert_eq!(Difficulty::load(&path), None);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn keys_move_and_adjust() {
+        let mut menu = Menu::new(Difficulty::Random, true);
+        assert_eq!(menu.handle(KeyCode::Up), MenuEvent::Continue);
+        assert_eq!(menu.selected(), Difficulty::Random);
+        menu.handle(KeyCode::Down);
+        menu.handle(KeyCode::Down);
+        for _ in 0..10 {
+            menu.handle(KeyCode::Right);
+        }
+        assert_eq!(menu.selected(), Difficulty::Depth(8));
+        menu.handle(KeyCode::Char('4'));
+        menu.handle(KeyCode::Left);
+        assert_eq!(
+            menu.handle(KeyCode::Enter),
+            MenuEvent::Chosen(Difficulty::Timed(Duration::from_secs(1)))
+        );
+        assert_eq!(menu.handle(KeyCode::Esc), MenuEvent::Quit);
+        // sans recherche alpha-beta, le niveau de profondeur disparaît
+        let menu = Menu::new(Difficulty::Depth(4), false);
+        assert_eq!(menu.lines().len(), 6);
+        assert_eq!(menu.selected(), Difficulty::Tactical);
+    }
+}
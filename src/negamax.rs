@@ -0,0 +1,274 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    bitboard::{Bitboard, LINE_MASKS},
+    error::P4Error,
+    input::Input,
+    logic::Thinker,
+    plate::Plate,
+    player::{PlayerID, TurnOrder},
+};
+
+/// Une victoire en `n` demi-coups vaut `WIN_SCORE - n`
+pub(crate) const WIN_SCORE: i32 = 1_000_000;
+/// Valeur d'une ligne selon le nombre de jetons d'un seul joueur dessus
+const LINE_WEIGHTS: [i32; 5] = [0, 1, 8, 64, 0];
+
+/// Negamax avec élagage alpha-beta, approfondissement itératif
+/// jusqu'à `depth` demi-coups ou jusqu'à épuisement de `time_budget`.
+/// Ne joue qu'à deux, sur le cube standard.
+#[derive(Clone, Debug)]
+pub struct Negamax {
+    pub player_id: PlayerID,
+    depth: u8,
+    time_budget: Duration,
+}
+
+impl Negamax {
+    /// Piliers du centre d'abord, puis les coins, puis les bords
+    const MOVE_ORDER: [u8; 16] = [5, 6, 9, 10, 0, 3, 12, 15, 1, 2, 4, 7, 8, 11, 13, 14];
+
+    pub fn new(player_id: PlayerID, depth: u8, time_budget: Duration) -> Negamax {
+        Negamax {
+            player_id,
+            depth: depth.max(1),
+            time_budget,
+        }
+    }
+
+    fn ordered_moves(bb: &Bitboard) -> impl Iterator<Item = u8> + '_ {
+        Self::MOVE_ORDER.into_iter().filter(|p| bb.playable(*p))
+    }
+
+    /// Évaluation du point de vue de `me`
+    fn evaluate(bb: &Bitboard, me: PlayerID) -> i32 {
+        let opponent = TurnOrder::default().next(me);
+        let (mine, theirs) = (bb.get_player_board(me), bb.get_player_board(opponent));
+        LINE_MASKS
+            .iter()
+            .map(
+                |m| match ((mine & m).count_ones(), (theirs & m).count_ones()) {
+                    (n, 0) => LINE_WEIGHTS[n as usize],
+                    (0, n) => -LINE_WEIGHTS[n as usize],
+                    _ => 0,
+                },
+            )
+            .sum()
+    }
+
+    /// `None` si le temps est écoulé
+    fn negamax(
+        bb: &mut Bitboard,
+        player: PlayerID,
+        depth: u8,
+        ply: i32,
+        mut alpha: i32,
+        beta: i32,
+        deadline: Instant,
+    ) -> Option<i32> {
+        if Instant::now() >= deadline {
+            return None;
+        }
+        if depth == 0 {
+            return Some(Self::evaluate(bb, player));
+        }
+        let mut best = None;
+        for pillar in Self::MOVE_ORDER {
+            if !bb.playable(pillar) {
+                continue;
+            }
+            let score = Self::score_move(bb, pillar, player, depth, ply, alpha, beta, deadline)?;
+            best = Some(best.map_or(score, |b: i32| b.max(score)));
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        // aucun coup possible : le cube est plein
+        Some(best.unwrap_or(0))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn score_move(
+        bb: &mut Bitboard,
+        pillar: u8,
+        player: PlayerID,
+        depth: u8,
+        ply: i32,
+        alpha: i32,
+        beta: i32,
+        deadline: Instant,
+    ) -> Option<i32> {
+        let cell = bb.play(pillar, player).ok()?;
+        let score = if bb.wins_at(cell) {
+            Some(WIN_SCORE - ply)
+        } else {
+            let opponent = TurnOrder::default().next(player);
+            Self::negamax(bb, opponent, depth - 1, ply + 1, -beta, -alpha, deadline).map(|s| -s)
+        };
+        bb.undo(pillar);
+        score
+    }
+
+    fn search_root(&self, bb: &mut Bitboard, depth: u8, deadline: Instant) -> Option<(u8, i32)> {
+        let mut alpha = -WIN_SCORE - 1;
+        let mut best = None;
+        for pillar in Self::MOVE_ORDER {
+            if !bb.playable(pillar) {
+                continue;
+            }
+            let score = Self::score_move(
+                bb,
+                pillar,
+                self.player_id,
+                depth,
+                1,
+                alpha,
+                WIN_SCORE + 1,
+                deadline,
+            )?;
+            if score > alpha {
+                alpha = score;
+                best = Some((pillar, score));
+            }
+        }
+        best
+    }
+
+    /// Le score de chaque coup possible, à la profondeur complète : sans élagage
+    /// à la racine, les coups moins bons que le meilleur gardent leur vrai score
+    pub fn score_moves(&self, plt: &Plate) -> Result<Vec<(Input, i32)>, P4Error> {
+        if !plt.dimensions().is_standard() {
+            return Err(P4Error::UnsupportedDimensions(plt.dimensions()));
+        }
+        if self.player_id.index() >= 2 {
+            return Err(P4Error::UnsupportedPlayers);
+        }
+        let deadline = Instant::now() + self.time_budget;
+        let mut bb = Bitboard::from(plt);
+        let (alpha, beta) = (-WIN_SCORE - 1, WIN_SCORE + 1);
+        let mut scores = Vec::new();
+        for pillar in Self::MOVE_ORDER {
+            if !bb.playable(pillar) {
+                continue;
+            }
+            let score = Self::score_move(
+                &mut bb,
+                pillar,
+                self.player_id,
+                self.depth,
+                1,
+                alpha,
+                beta,
+                deadline,
+            )
+            .ok_or(P4Error::Unsolved)?;
+            scores.push((Input::from(pillar), score));
+        }
+        Ok(scores)
+    }
+}
+
+impl Thinker for Negamax {
+    fn think(&self, plt: &Plate) -> Result<Input, P4Error> {
+        if !plt.dimensions().is_standard() {
+            return Err(P4Error::UnsupportedDimensions(plt.dimensions()));
+        }
+        if self.player_id.index() >= 2 {
+            return Err(P4Error::UnsupportedPlayers);
+        }
+        let deadline = Instant::now() + self.time_budget;
+        let mut bb = Bitboard::from(plt);
+        let mut best = Self::ordered_moves(&bb)
+            .next()
+            .ok_or(P4Error::NoLegalMove)?;
+        for depth in 1..=self.depth {
+            match self.search_root(&mut bb, depth, deadline) {
+                Some((pillar, score)) => {
+                    log::debug!(
+                        "Negamax depth {} : {} (score {})",
+                        depth,
+                        Input::from(pillar),
+                        score
+                    );
+                    best = pillar;
+                    if score.abs() >= WIN_SCORE - 64 {
+                        break;
+                    }
+                }
+                None => {
+                    log::debug!("Negamax ran out of time at depth {}", depth);
+                    break;
+                }
+            }
+        }
+        Ok(Input::from(best))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coord::NumberCoord;
+
+    fn bot() -> Negamax {
+        Negamax::new(PlayerID::P2, 3, Duration::from_secs(5))
+    }
+
+    #[test]
+    fn takes_an_immediate_win() {
+        let mut plt = Plate::default();
+        for i in [0, 1, 2] {
+            plt.add_player(NumberCoord::new(i), PlayerID::P2).unwrap();
+            plt.add_player(NumberCoord::new(i + 4), PlayerID::P1)
+                .unwrap();
+        }
+        plt.add_player(NumberCoord::new(6), PlayerID::P1).unwrap();
+        assert_eq!(bot().think(&plt).unwrap(), Input::from(3));
+    }
+
+    #[test]
+    fn blocks_an_immediate_threat() {
+        let mut plt = Plate::default();
+        for i in [12, 13, 14] {
+            plt.add_player(NumberCoord::new(i), PlayerID::P1).unwrap();
+        }
+        plt.add_player(NumberCoord::new(5), PlayerID::P2).unwrap();
+        plt.add_player(NumberCoord::new(10), PlayerID::P2).unwrap();
+        assert_eq!(bot().think(&plt).unwrap(), Input::from(15));
+    }
+
+    #[test]
+    fn full_plate_has_no_move() {
+        let mut plt = Plate::default();
+        let mut pl = PlayerID::P1;
+        for i in 0..16 {
+            for _ in 0..4 {
+                plt.add_player(NumberCoord::new(i), pl).unwrap();
+                pl = TurnOrder::default().next(pl);
+            }
+        }
+        assert!(matches!(bot().think(&plt), Err(P4Error::NoLegalMove)));
+    }
+
+    #[test]
+    fn every_move_is_scored() {
+        let mut plt = Plate::default();
+        for i in [0, 1, 2] {
+            plt.add_player(NumberCoord::new(i), PlayerID::P2).unwrap();
+            plt.add_player(NumberCoord::new(i + 4), PlayerID::P1)
+                .unwrap();
+        }
+        plt.add_player(NumberCoord::new(6), PlayerID::P1).unwrap();
+        let scores = bot().score_moves(&plt).unwrap();
+        assert_eq!(scores.len(), 16);
+        let best = scores.iter().max_by_key(|(_, s)| *s).unwrap();
+        assert_eq!(*best, (Input::from(3), WIN_SCORE - 1));
+        // ailleurs, P1 gagne en 7 au tour suivant
+        let elsewhere = scores
+            .iter()
+            .find(|(mv, _)| *mv == Input::from(10))
+            .unwrap();
+        assert_eq!(elsewhere.1, -(WIN_SCORE - 2));
+    }
+}
@@ -0,0 +1,721 @@
+//! Parties en réseau. Le serveur (`p4_server`) tient la partie qui fait foi,
+//! chaque client y joue une place. Le protocole est une ligne par message :
+//!
+//! - `WELCOME <place> <joueurs> <taille>` : le serveur attribue une place au client
+//! - `MOVE <coup>` : un coup, du client vers le serveur puis du serveur vers les autres
+//! - `OK` / `REJECTED <raison>` : la réponse du serveur au client qui a joué
+//! - `LEFT <place>` : un joueur s'est déconnecté, la partie s'arrête
+//!
+//! Les spectateurs se connectent à une seconde adresse. Ils reçoivent
+//! `WATCH <joueurs> <taille> <coups...>`, la partie jouée jusque-là,
+//! puis les mêmes `MOVE` et `LEFT` que les joueurs, et n'envoient rien.
+use std::{
+    cell::RefCell,
+    io::{BufRead, BufReader, ErrorKind, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    rc::Rc,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crossterm::event::{self, Event, KeyCode};
+
+use crate::{
+    dimensions::Dimensions,
+    engine::Engine,
+    error::P4Error,
+    input::Input,
+    logic::{Action, Thinker},
+    notation::{parse_notation, to_notation},
+    plate::{Outcome, Plate},
+    player::{PlayerID, TurnOrder},
+    Game,
+};
+
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:4444";
+pub const DEFAULT_SPECTATOR_ADDRESS: &str = "127.0.0.1:4445";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    Welcome {
+        seat: PlayerID,
+        players: TurnOrder,
+        dims: Dimensions,
+    },
+    Move(Input),
+    Accepted,
+    Rejected(String),
+    Left(PlayerID),
+    Watch {
+        players: TurnOrder,
+        dims: Dimensions,
+        moves: Vec<Input>,
+    },
+}
+
+/// Les places sont numérotées à partir de 1, comme `--first`
+fn parse_seat(s: &str) -> Option<PlayerID> {
+    let seat: usize = s.parse().ok()?;
+    (1..=TurnOrder::MAX_PLAYERS as usize)
+        .contains(&seat)
+        .then(|| PlayerID::from_index(seat - 1))
+}
+
+impl std::fmt::Display for Message {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Message::Welcome {
+                seat,
+                players,
+                dims,
+            } => write!(
+                f,
+                "WELCOME {} {} {}",
+                seat.index() + 1,
+                players.count(),
+                dims
+            ),
+            Message::Move(mv) => write!(f, "MOVE {}", mv),
+            Message::Accepted => write!(f, "OK"),
+            Message::Rejected(reason) => write!(f, "REJECTED {}", reason),
+            Message::Left(seat) => write!(f, "LEFT {}", seat.index() + 1),
+            Message::Watch {
+                players,
+                dims,
+                moves,
+            } => {
+                write!(f, "WATCH {} {}", players.count(), dims)?;
+                if !moves.is_empty() {
+                    write!(f, " {}", to_notation(moves))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl FromStr for Message {
+    type Err = P4Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || P4Error::ProtocolError(s.to_owned());
+        let (word, rest) = s.split_once(' ').unwrap_or((s, ""));
+        Ok(match word {
+            "WELCOME" => {
+                let mut args = rest.split_whitespace();
+                let (Some(seat), Some(players), Some(dims), None) =
+                    (args.next(), args.next(), args.next(), args.next())
+                else {
+                    return Err(err());
+                };
+                Message::Welcome {
+                    seat: parse_seat(seat).ok_or_else(err)?,
+                    players: TurnOrder::new(players.parse().map_err(|_| err())?)?,
+                    dims: dims.parse()?,
+                }
+            }
+            "MOVE" => match parse_notation(rest)?.as_slice() {
+                [mv] => Message::Move(mv.clone()),
+                _ => return Err(err()),
+            },
+            "OK" if rest.is_empty() => Message::Accepted,
+            "REJECTED" => Message::Rejected(rest.to_owned()),
+            "LEFT" => Message::Left(parse_seat(rest).ok_or_else(err)?),
+            "WATCH" => {
+                let mut args = rest.splitn(3, ' ');
+                let (Some(players), Some(dims)) = (args.next(), args.next()) else {
+                    return Err(err());
+                };
+                Message::Watch {
+                    players: TurnOrder::new(players.parse().map_err(|_| err())?)?,
+                    dims: dims.parse()?,
+                    moves: parse_notation(args.next().unwrap_or_default())?,
+                }
+            }
+            _ => return Err(err()),
+        })
+    }
+}
+
+/// Une connexion TCP, un `Message` par ligne
+pub struct Link {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    /// Le début d'une ligne interrompue par `recv_timeout`
+    partial: String,
+}
+
+impl Link {
+    pub fn new(stream: TcpStream) -> Result<Link, P4Error> {
+        let writer = stream.try_clone().map_err(P4Error::NetworkError)?;
+        Ok(Link {
+            reader: BufReader::new(stream),
+            writer,
+            partial: String::new(),
+        })
+    }
+
+    pub fn connect(address: impl ToSocketAddrs) -> Result<Link, P4Error> {
+        Link::new(TcpStream::connect(address).map_err(P4Error::NetworkError)?)
+    }
+
+    pub fn send(&mut self, msg: &Message) -> Result<(), P4Error> {
+        log::debug!("Sending {}", msg);
+        writeln!(self.writer, "{}", msg).map_err(P4Error::NetworkError)
+    }
+
+    pub fn recv(&mut self) -> Result<Message, P4Error> {
+        loop {
+            if let Some(msg) = self.recv_timeout(None)? {
+                return Ok(msg);
+            }
+        }
+    }
+
+    /// Attend un message au plus `timeout`, sans limite avec `None`
+    pub fn recv_timeout(&mut self, timeout: Option<Duration>) -> Result<Option<Message>, P4Error> {
+        self.reader
+            .get_ref()
+            .set_read_timeout(timeout)
+            .map_err(P4Error::NetworkError)?;
+        match self.reader.read_line(&mut self.partial) {
+            Ok(0) => Err(P4Error::Disconnected),
+            Ok(_) => {
+                let line = std::mem::take(&mut self.partial);
+                log::debug!("Received {}", line.trim_end());
+                line.trim_end().parse().map(Some)
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => Ok(None),
+            Err(e) => Err(P4Error::NetworkError(e)),
+        }
+    }
+}
+
+/// Un joueur à l'autre bout du réseau : ses coups arrivent par `MOVE`.
+/// Côté serveur c'est un client, côté client un adversaire relayé par le serveur.
+pub struct RemoteThinker {
+    link: Rc<RefCell<Link>>,
+}
+
+impl RemoteThinker {
+    pub fn new(link: Rc<RefCell<Link>>) -> RemoteThinker {
+        RemoteThinker { link }
+    }
+}
+
+impl Thinker for RemoteThinker {
+    fn think(&self, _plt: &Plate) -> Result<Input, P4Error> {
+        match self.link.borrow_mut().recv()? {
+            Message::Move(mv) => Ok(mv),
+            Message::Left(seat) => Err(P4Error::PlayerLeft(seat)),
+            other => Err(P4Error::ProtocolError(other.to_string())),
+        }
+    }
+}
+
+/// Un joueur vu par un spectateur : comme `RemoteThinker`, ses coups arrivent
+/// du serveur, mais le spectateur peut partir avec Échap ou `q` en attendant
+pub struct Spectator {
+    link: Rc<RefCell<Link>>,
+}
+
+impl Spectator {
+    /// L'attente d'un coup entre deux coups d'œil au clavier
+    const POLL: Duration = Duration::from_millis(50);
+
+    pub fn new(link: Rc<RefCell<Link>>) -> Spectator {
+        Spectator { link }
+    }
+
+    fn wait_move(&self) -> Result<Input, P4Error> {
+        loop {
+            while event::poll(Duration::ZERO)? {
+                if let Event::Key(key) = event::read()? {
+                    if matches!(key.code, KeyCode::Esc | KeyCode::Char('q')) {
+                        return Err(P4Error::Stopped);
+                    }
+                }
+            }
+            match self.link.borrow_mut().recv_timeout(Some(Self::POLL))? {
+                Some(Message::Move(mv)) => return Ok(mv),
+                Some(Message::Left(seat)) => return Err(P4Error::PlayerLeft(seat)),
+                Some(other) => return Err(P4Error::ProtocolError(other.to_string())),
+                None => {}
+            }
+        }
+    }
+}
+
+impl Thinker for Spectator {
+    fn think(&self, _plt: &Plate) -> Result<Input, P4Error> {
+        crossterm::terminal::enable_raw_mode()?;
+        let res = self.wait_move();
+        crossterm::terminal::disable_raw_mode()?;
+        res
+    }
+}
+
+/// Un joueur de ce terminal dans une partie en réseau : chacun de ses coups
+/// est soumis au serveur, qui peut le refuser
+pub struct LocalThinker {
+    inner: Box<dyn Thinker>,
+    link: Rc<RefCell<Link>>,
+}
+
+impl LocalThinker {
+    pub fn new(inner: Box<dyn Thinker>, link: Rc<RefCell<Link>>) -> LocalThinker {
+        LocalThinker { inner, link }
+    }
+
+    fn tell(&self, message: &str) -> Result<(), P4Error> {
+        if self.inner.is_human() {
+            Game::message_display(format!("{:<40}", message).as_str())?;
+        }
+        Ok(())
+    }
+}
+
+impl Thinker for LocalThinker {
+    fn think(&self, plt: &Plate) -> Result<Input, P4Error> {
+        self.think_after(plt, &[])
+    }
+
+    fn think_after(&self, plt: &Plate, history: &[Input]) -> Result<Input, P4Error> {
+        match self.act(plt, history)? {
+            Action::Play(mv) => Ok(mv),
+            action => unreachable!("{:?} is never sent over the network", action),
+        }
+    }
+
+    fn act(&self, plt: &Plate, history: &[Input]) -> Result<Action, P4Error> {
+        loop {
+            let mv = match self.inner.act(plt, history)? {
+                Action::Play(mv) => mv,
+                Action::Undo | Action::Redo => {
+                    self.tell("Pas de retour en arrière en réseau")?;
+                    continue;
+                }
+            };
+            let mut link = self.link.borrow_mut();
+            link.send(&Message::Move(mv.clone()))?;
+            match link.recv()? {
+                Message::Accepted => return Ok(Action::Play(mv)),
+                Message::Rejected(reason) if self.inner.is_human() => {
+                    drop(link);
+                    self.tell(&format!("Coup refusé : {}", reason))?;
+                }
+                Message::Left(seat) => return Err(P4Error::PlayerLeft(seat)),
+                other => return Err(P4Error::ProtocolError(other.to_string())),
+            }
+        }
+    }
+
+    fn is_human(&self) -> bool {
+        self.inner.is_human()
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.inner.name()
+    }
+}
+
+/// Ceux qui regardent la partie, partagés avec le fil qui accueille les nouveaux
+#[derive(Clone)]
+struct Spectators(Arc<Mutex<Audience>>);
+
+struct Audience {
+    players: TurnOrder,
+    dims: Dimensions,
+    /// La partie jusqu'ici, envoyée à chaque nouveau spectateur
+    moves: Vec<Input>,
+    links: Vec<Link>,
+}
+
+impl Spectators {
+    fn new(dims: Dimensions, players: TurnOrder) -> Spectators {
+        Spectators(Arc::new(Mutex::new(Audience {
+            players,
+            dims,
+            moves: Vec::new(),
+            links: Vec::new(),
+        })))
+    }
+
+    /// Accueille les spectateurs de `listener` pendant toute la partie
+    fn accept(&self, listener: TcpListener) {
+        let spectators = self.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let res = stream
+                    .map_err(P4Error::NetworkError)
+                    .and_then(|stream| spectators.welcome(stream));
+                if let Err(e) = res {
+                    log::warn!("A spectator could not join : {}", e);
+                }
+            }
+        });
+    }
+
+    fn welcome(&self, stream: TcpStream) -> Result<(), P4Error> {
+        let address = stream.peer_addr().map_err(P4Error::NetworkError)?;
+        let mut link = Link::new(stream)?;
+        // le verrou est gardé jusqu'à l'ajout : aucun coup ne peut se glisser entre les deux
+        let mut audience = self.0.lock().expect("A spectator thread panicked");
+        link.send(&Message::Watch {
+            players: audience.players,
+            dims: audience.dims,
+            moves: audience.moves.clone(),
+        })?;
+        audience.links.push(link);
+        log::info!("{} is watching the game", address);
+        Ok(())
+    }
+
+    /// Les spectateurs partis sont oubliés
+    fn broadcast(&self, msg: &Message) {
+        let mut audience = self.0.lock().expect("A spectator thread panicked");
+        if let Message::Move(mv) = msg {
+            audience.moves.push(mv.clone());
+        }
+        audience.links.retain_mut(|link| link.send(msg).is_ok());
+    }
+}
+
+/// Attend un client par place puis arbitre la partie jusqu'au bout.
+/// Si un client part, les autres sont prévenus par `LEFT`.
+/// Les spectateurs de `spectators` peuvent arriver à tout moment.
+pub fn serve(
+    listener: &TcpListener,
+    spectators: Option<TcpListener>,
+    dims: Dimensions,
+    players: TurnOrder,
+) -> Result<Outcome, P4Error> {
+    let audience = Spectators::new(dims, players);
+    if let Some(listener) = spectators {
+        audience.accept(listener);
+    }
+    let mut links = Vec::new();
+    for seat in players.players() {
+        let (stream, address) = listener.accept().map_err(P4Error::NetworkError)?;
+        log::info!("{} joined as {:?}", address, seat);
+        let mut link = Link::new(stream)?;
+        link.send(&Message::Welcome {
+            seat,
+            players,
+            dims,
+        })?;
+        links.push(Rc::new(RefCell::new(link)));
+    }
+    let thinkers: Vec<RemoteThinker> = links
+        .iter()
+        .map(|link| RemoteThinker::new(link.clone()))
+        .collect();
+    let others = |seat: PlayerID| {
+        links
+            .iter()
+            .enumerate()
+            .filter(move |(i, _)| *i != seat.index())
+            .map(|(_, link)| link)
+    };
+
+    let mut engine = Engine::with_players(dims, players);
+    while engine.outcome() == Outcome::Ongoing {
+        let seat = engine.current_player();
+        let mv = match engine.ask(&thinkers[seat.index()]) {
+            Ok(mv) => mv,
+            Err(e) => {
+                log::warn!("{:?} left the game : {}", seat, e);
+                for link in others(seat) {
+                    // ceux qui sont déjà partis n'ont plus besoin d'être prévenus
+                    let _ = link.borrow_mut().send(&Message::Left(seat));
+                }
+                audience.broadcast(&Message::Left(seat));
+                return Err(e);
+            }
+        };
+        match engine.play(mv.clone()) {
+            Ok(_) => {
+                log::info!("{:?} played {}", seat, mv);
+                // les spectateurs d'abord : un joueur qui voit le coup sait qu'un
+                // nouveau spectateur le verra aussi
+                audience.broadcast(&Message::Move(mv.clone()));
+                links[seat.index()].borrow_mut().send(&Message::Accepted)?;
+                for link in others(seat) {
+                    link.borrow_mut().send(&Message::Move(mv.clone()))?;
+                }
+            }
+            Err(e) => links[seat.index()]
+                .borrow_mut()
+                .send(&Message::Rejected(e.to_string()))?,
+        }
+    }
+    Ok(engine.outcome())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    /// Joue les coups donnés puis se déconnecte
+    struct Scripted(RefCell<Vec<Input>>);
+
+    impl Thinker for Scripted {
+        fn think(&self, _plt: &Plate) -> Result<Input, P4Error> {
+            self.0.borrow_mut().pop().ok_or(P4Error::NoLegalMove)
+        }
+    }
+
+    /// Un client assis à sa place : le serveur l'a accueilli
+    struct Seated {
+        link: Link,
+        seat: PlayerID,
+        players: TurnOrder,
+        dims: Dimensions,
+    }
+
+    /// Attend `WELCOME` : le client suivant aura la place d'après
+    fn sit(address: std::net::SocketAddr) -> Result<Seated, P4Error> {
+        let mut link = Link::connect(address)?;
+        match link.recv()? {
+            Message::Welcome {
+                seat,
+                players,
+                dims,
+            } => Ok(Seated {
+                link,
+                seat,
+                players,
+                dims,
+            }),
+            _ => Err(P4Error::ProtocolError(String::from("no welcome"))),
+        }
+    }
+
+    /// Un client qui joue `moves` à sa place et suit les coups des autres
+    fn client(seated: Seated, moves: &str) -> Result<Engine, P4Error> {
+        let Seated {
+            link,
+            seat,
+            players,
+            dims,
+        } = seated;
+        let link = Rc::new(RefCell::new(link));
+        let mut moves = parse_notation(moves)?;
+        moves.reverse();
+        let local = LocalThinker::new(Box::new(Scripted(RefCell::new(moves))), link.clone());
+        let remote = RemoteThinker::new(link);
+        let mut engine = Engine::with_players(dims, players);
+        while engine.outcome() == Outcome::Ongoing {
+            let mv = if engine.current_player() == seat {
+                engine.ask(&local)?
+            } else {
+                engine.ask(&remote)?
+            };
+            engine.play(mv)?;
+        }
+        Ok(engine)
+    }
+
+    /// Un spectateur qui suit la partie jusqu'au bout
+    fn spectator(link: Link) -> Result<Engine, P4Error> {
+        let link = Rc::new(RefCell::new(link));
+        let Message::Watch {
+            players,
+            dims,
+            moves,
+        } = link.borrow_mut().recv()?
+        else {
+            return Err(P4Error::ProtocolError(String::from("no position")));
+        };
+        let mut engine = Engine::with_players(dims, players);
+        for mv in moves {
+            engine.play(mv)?;
+        }
+        let remote = RemoteThinker::new(link);
+        while engine.outcome() == Outcome::Ongoing {
+            let mv = engine.ask(&remote)?;
+            engine.play(mv)?;
+        }
+        Ok(engine)
+    }
+
+    #[test]
+    fn messages_round_trip() {
+        for line in [
+            "WATCH 2 4x4x4:4",
+            "WATCH 3 5x5x5:4 A0 B2 E4",
+            "WELCOME 2 3 5x5x5:4",
+            "MOVE B3",
+            "OK",
+            "REJECTED The game is already over",
+            "LEFT 1",
+        ] {
+            assert_eq!(line.parse::<Message>().unwrap().to_string(), line);
+        }
+        for bad in ["WELCOME 5 2 4x4x4:4", "MOVE", "MOVE A0 A1", "HELLO"] {
+            assert!(bad.parse::<Message>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn recv_timeout_keeps_cut_lines() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut link = Link::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut server, _) = listener.accept().unwrap();
+        let wait = Some(Duration::from_millis(20));
+        assert_eq!(link.recv_timeout(wait).unwrap(), None);
+        // la ligne arrive en deux fois, de part et d'autre d'une attente
+        server.write_all(b"MOV").unwrap();
+        assert_eq!(link.recv_timeout(wait).unwrap(), None);
+        server.write_all(b"E B3\nOK\n").unwrap();
+        let arrival = Some(Duration::from_secs(5));
+        assert!(matches!(
+            link.recv_timeout(arrival),
+            Ok(Some(Message::Move(_)))
+        ));
+        assert_eq!(link.recv().unwrap(), Message::Accepted);
+        drop(server);
+        assert!(matches!(
+            link.recv_timeout(arrival),
+            Err(P4Error::Disconnected)
+        ));
+    }
+
+    #[test]
+    fn game_over_localhost() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            serve(&listener, None, Dimensions::STANDARD, TurnOrder::default())
+        });
+        let one = sit(address).unwrap();
+        assert_eq!(one.seat, PlayerID::P1);
+        let one = thread::spawn(move || client(one, "A0 A0 A0 A0"));
+        let two = client(sit(address).unwrap(), "B0 B0 B0").unwrap();
+        let one = one.join().unwrap().unwrap();
+        assert!(matches!(
+            server.join().unwrap(),
+            Ok(Outcome::Win(PlayerID::P1, _))
+        ));
+        assert_eq!(one.to_notation(), two.to_notation());
+    }
+
+    #[test]
+    fn spectators_follow_the_game() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let watchers = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let watch = watchers.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            serve(
+                &listener,
+                Some(watchers),
+                Dimensions::STANDARD,
+                TurnOrder::default(),
+            )
+        });
+        // le spectateur est enregistré avant le premier coup
+        let mut early = Link::connect(watch).unwrap();
+        let first = early.recv().unwrap();
+        assert_eq!(first.to_string(), "WATCH 2 4x4x4:4");
+        let one = sit(address).unwrap();
+        let one = thread::spawn(move || client(one, "A0 A0 A0 A0"));
+        let two = client(sit(address).unwrap(), "B0 B0 B0").unwrap();
+        one.join().unwrap().unwrap();
+        server.join().unwrap().unwrap();
+
+        let mut seen = Engine::with_players(Dimensions::STANDARD, TurnOrder::default());
+        let remote = RemoteThinker::new(Rc::new(RefCell::new(early)));
+        while seen.outcome() == Outcome::Ongoing {
+            let mv = seen.ask(&remote).unwrap();
+            seen.play(mv).unwrap();
+        }
+        assert_eq!(seen.to_notation(), two.to_notation());
+        // arrivé après la fin, on voit encore la position finale
+        let late = spectator(Link::connect(watch).unwrap()).unwrap();
+        assert_eq!(late.to_notation(), two.to_notation());
+    }
+
+    #[test]
+    fn late_spectator_gets_the_position() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let watchers = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let watch = watchers.local_addr().unwrap();
+        thread::spawn(move || {
+            serve(
+                &listener,
+                Some(watchers),
+                Dimensions::STANDARD,
+                TurnOrder::default(),
+            )
+        });
+        // le second joueur attend que le spectateur soit là pour finir la partie
+        let (ready, wait) = std::sync::mpsc::channel();
+        let (played, seen_three) = std::sync::mpsc::channel();
+        let one = sit(address).unwrap();
+        let one = thread::spawn(move || client(one, "A0 A0 A0 A0"));
+        let two = sit(address).unwrap();
+        let two = thread::spawn(move || -> Result<Engine, P4Error> {
+            let seat = two.seat;
+            assert_eq!(seat, PlayerID::P2);
+            let link = Rc::new(RefCell::new(two.link));
+            let mut engine = Engine::new();
+            let remote = RemoteThinker::new(link.clone());
+            let local = LocalThinker::new(
+                Box::new(Scripted(RefCell::new(parse_notation("B0 B0 B0")?))),
+                link,
+            );
+            engine.play(engine.ask(&remote)?)?;
+            engine.play(engine.ask(&local)?)?;
+            engine.play(engine.ask(&remote)?)?;
+            played.send(()).unwrap();
+            wait.recv().unwrap();
+            while engine.outcome() == Outcome::Ongoing {
+                let mv = if engine.current_player() == seat {
+                    engine.ask(&local)?
+                } else {
+                    engine.ask(&remote)?
+                };
+                engine.play(mv)?;
+            }
+            Ok(engine)
+        });
+        seen_three.recv().unwrap();
+        let mut link = Link::connect(watch).unwrap();
+        let Message::Watch { moves, .. } = link.recv().unwrap() else {
+            panic!("no position");
+        };
+        ready.send(()).unwrap();
+        let mut seen = Engine::new();
+        for mv in moves {
+            seen.play(mv).unwrap();
+        }
+        // le premier joueur a déjà répondu, c'est au tour du second
+        assert_eq!(seen.to_notation(), "A0 B0 A0");
+        one.join().unwrap().unwrap();
+        let two = two.join().unwrap().unwrap();
+        assert_eq!(two.to_notation(), "A0 B0 A0 B0 A0 B0 A0");
+    }
+
+    #[test]
+    fn disconnect_is_reported() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            serve(&listener, None, Dimensions::STANDARD, TurnOrder::default())
+        });
+        let one = sit(address).unwrap();
+        let one = thread::spawn(move || client(one, "A0 A0"));
+        // le second joueur part après son premier coup
+        let two = client(sit(address).unwrap(), "B0");
+        assert!(matches!(two, Err(P4Error::NoLegalMove)));
+        assert!(matches!(
+            one.join().unwrap(),
+            Err(P4Error::PlayerLeft(PlayerID::P2))
+        ));
+        assert!(matches!(server.join().unwrap(), Err(P4Error::Disconnected)));
+    }
+}
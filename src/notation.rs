@@ -0,0 +1,141 @@
+//! Notation d'une partie : la liste des coups séparés par des espaces,
+//! au format des `LetterCoord` (`A0 B2 C3 ...`).
+//!
+//! Une partie enregistrée commence par un en-tête `size <LxNxH:A> players <N>`,
+//! comme la commande `position` du protocole des moteurs.
+use std::str::FromStr;
+
+use itertools::Itertools;
+
+use crate::{
+    coord::{Coord, LetterCoord},
+    dimensions::Dimensions,
+    error::P4Error,
+    input::Input,
+    player::TurnOrder,
+};
+
+pub fn to_notation(moves: &[Input]) -> String {
+    moves.iter().join(" ")
+}
+
+pub fn parse_notation(s: &str) -> Result<Vec<Input>, P4Error> {
+    s.split_whitespace().map(parse_move).collect()
+}
+
+fn parse_move(mv: &str) -> Result<Input, P4Error> {
+    LetterCoord::from_str(mv).map(|lc| Input::from(lc.to_ln_coord()))
+}
+
+/// Une partie enregistrée, en-tête compris
+pub fn to_game_notation(dims: Dimensions, players: TurnOrder, moves: &[Input]) -> String {
+    format!(
+        "size {} players {}\n{}",
+        dims,
+        players.count(),
+        to_notation(moves)
+    )
+}
+
+/// Relit une partie enregistrée. Sans en-tête, comme dans les anciennes
+/// parties, elle se joue à deux sur le cube standard
+pub fn parse_game_notation(s: &str) -> Result<(Dimensions, TurnOrder, Vec<Input>), P4Error> {
+    let mut dims = Dimensions::STANDARD;
+    let mut players = TurnOrder::default();
+    let mut words = s.split_whitespace().peekable();
+    while let Some(&key) = words.peek() {
+        if key != "size" && key != "players" {
+            break;
+        }
+        words.next();
+        let value = words
+            .next()
+            .ok_or_else(|| P4Error::InvalidNotation(key.to_owned()))?;
+        match key {
+            "size" => dims = value.parse()?,
+            _ => {
+                let count = value
+                    .parse()
+                    .map_err(|_| P4Error::InvalidNotation(value.to_owned()))?;
+                players = TurnOrder::new(count)?;
+            }
+        }
+    }
+    let moves = words.map(parse_move).collect::<Result<_, _>>()?;
+    Ok((dims, players, moves))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bitboard::Bitboard,
+        engine::Engine,
+        plate::Plate,
+        player::{PlayerID, TurnOrder},
+    };
+
+    #[test]
+    fn notation_round_trip() {
+        let game = "A0 B2 C3 A0 D1 b2";
+        let moves = parse_notation(game).unwrap();
+        assert_eq!(moves[5], Input::from(9));
+        assert_eq!(to_notation(&moves), "A0 B2 C3 A0 D1 B2");
+        assert_eq!(parse_notation(&to_notation(&moves)).unwrap(), moves);
+    }
+
+    #[test]
+    fn replayed_game_matches_plate() {
+        let engine = Engine::from_notation("A0 B2 C3 A0 D1 B2").unwrap();
+        let mut plt = Plate::default();
+        let mut player = PlayerID::P1;
+        for n in [0, 9, 14, 0, 7, 9] {
+            plt.add_player(Input::from(n), player).unwrap();
+            player = TurnOrder::default().next(player);
+        }
+        assert_eq!(Bitboard::from(engine.plate()), Bitboard::from(&plt));
+        assert_eq!(
+            Engine::from_notation(&engine.to_notation())
+                .unwrap()
+                .history(),
+            engine.history()
+        );
+    }
+
+    #[test]
+    fn header_is_read_back() {
+        let dims: Dimensions = "5x5x5:4".parse().unwrap();
+        let players = TurnOrder::new(3).unwrap();
+        let moves = parse_notation("A0 E4 C2").unwrap();
+        let game = to_game_notation(dims, players, &moves);
+        assert_eq!(game, "size 5x5x5:4 players 3\nA0 E4 C2");
+        assert_eq!(parse_game_notation(&game).unwrap(), (dims, players, moves));
+        // les parties enregistrées sans en-tête
+        assert_eq!(
+            parse_game_notation("A0 B2\n").unwrap(),
+            (
+                Dimensions::STANDARD,
+                TurnOrder::default(),
+                parse_notation("A0 B2").unwrap()
+            )
+        );
+        assert!(parse_game_notation("size").is_err());
+        assert!(parse_game_notation("players 9 A0").is_err());
+        assert!(parse_game_notation("A0 size 4x4x4:4").is_err());
+    }
+
+    #[test]
+    fn bad_notation_is_rejected() {
+        assert!(matches!(
+            parse_notation("A0 J1"),
+            Err(P4Error::InvalidNotation(s)) if s == "J1"
+        ));
+        assert!(parse_notation("A8").is_err());
+        assert!(parse_notation("A").is_err());
+        // le cinquième jeton sur A0 déborde
+        assert!(matches!(
+            Engine::from_notation("A0 A0 A0 A0 A0"),
+            Err(P4Error::OverFilledPillar(_))
+        ));
+    }
+}
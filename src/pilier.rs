@@ -2,24 +2,29 @@ use std::io::{stdout, Write};
 
 use crate::coord::{Coord, Letter};
 use crate::error::P4Error;
+use crate::plate::Plate;
 use crate::player::PlayerID;
 use crate::utils::{DrawSquareAt, DrawSquareAtRawCoord, PrintAt};
 use crossterm::queue;
 
-#[derive(Clone, Copy, Debug)]
+/// Les jetons d'un pilier, du bas vers le haut
+#[derive(Clone, Debug)]
 pub struct Pillar {
-    pub data: [PlayerID; 4],
+    pub data: Vec<PlayerID>,
     height: u8,
 }
 
+/// Un étage, rangé comme les piliers du plateau
 pub struct Plane {
-    pub data: [PlayerID; 16],
+    pub data: Vec<PlayerID>,
+    pub width: u8,
+    pub depth: u8,
 }
 
 impl std::fmt::Display for Pillar {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "[")?;
-        for p in self.data.into_iter() {
+        for p in self.data.iter().copied() {
             if p == PlayerID::Empty {
                 write!(f, "v,")?;
             } else {
@@ -33,32 +38,38 @@ impl std::fmt::Display for Pillar {
 
 impl Default for Pillar {
     fn default() -> Self {
-        Pillar {
-            data: [PlayerID::Empty; 4],
-            height: 0,
-        }
+        Pillar::with_capacity(4)
     }
 }
 
 impl Pillar {
     #[allow(dead_code)]
     fn new(v: Vec<PlayerID>) -> Pillar {
-        debug_assert!(v.len() == 4);
-        let temp: [PlayerID; 4] = v.try_into().unwrap();
+        Pillar { data: v, height: 0 }
+    }
+
+    /// Un pilier vide de `capacity` étages
+    pub fn with_capacity(capacity: u8) -> Pillar {
         Pillar {
-            data: temp,
+            data: vec![PlayerID::Empty; capacity as usize],
             height: 0,
         }
     }
+
+    pub fn capacity(&self) -> u8 {
+        self.data.len() as u8
+    }
+
     #[allow(dead_code)]
     pub fn is_full(&self) -> bool {
-        self.height >= 4
+        self.height >= self.capacity()
     }
 
     fn validate(&self) {
         assert!(
-            self.height < 5,
-            "Hauteur trop haute !!!\n antendu : < 5, recu : {}",
+            self.height <= self.capacity(),
+            "Hauteur trop haute !!!\n antendu : <= {}, recu : {}",
+            self.capacity(),
             self.height
         );
         assert!(
@@ -72,7 +83,7 @@ impl Pillar {
 
     pub fn add_player(&mut self, p: PlayerID) -> Result<(), P4Error> {
         debug_assert!(p != PlayerID::Empty);
-        if self.height >= 4 {
+        if self.is_full() {
             return Err(P4Error::OverFilledPillar(None));
         }
         self.data[self.height as usize] = p;
@@ -81,6 +92,17 @@ impl Pillar {
         Ok(())
     }
 
+    /// Retire le jeton du haut du pilier
+    pub fn pop(&mut self) -> Result<PlayerID, P4Error> {
+        if self.height == 0 {
+            return Err(P4Error::EmptyPillar(None));
+        }
+        self.height -= 1;
+        let p = std::mem::take(&mut self.data[self.height as usize]);
+        self.validate();
+        Ok(p)
+    }
+
     pub fn get_height(&self) -> u8 {
         self.height.clone()
     }
@@ -89,18 +111,28 @@ impl Pillar {
         self.data[i].clone()
     }
 
-    pub fn draw(&self, origin: (u16, u16), pillarname: impl Coord) -> Result<(), P4Error> {
+    pub fn draw(
+        &self,
+        origin: (u16, u16),
+        pillarname: impl Coord,
+        highlighted: &[usize],
+    ) -> Result<(), P4Error> {
         let (l, n) = pillarname.to_ln_coord();
         queue!(
             std::io::stdout(),
             PrintAt(origin.0, origin.1, format!("{}{}", l, n).as_str())
         )
         .map_err(|e| P4Error::OutputInterfaceError(e))?;
-        for (i, d) in self.data.iter().rev().enumerate() {
-            let coord = (0, i as u16);
+        for (i, d) in self.data.iter().enumerate().rev() {
+            let color = if highlighted.contains(&i) {
+                Plate::WIN_COLOR
+            } else {
+                d.color()
+            };
+            let coord = (0, (self.data.len() - 1 - i) as u16);
             let x = origin.0 + coord.0;
             let y = origin.1 + coord.1 + 1;
-            queue!(std::io::stdout(), DrawSquareAtRawCoord(x, y, d.color()))?;
+            queue!(std::io::stdout(), DrawSquareAtRawCoord(x, y, color))?;
         }
         stdout()
             .flush()
@@ -110,12 +142,17 @@ impl Pillar {
 }
 
 impl Plane {
-    pub fn draw(&self, origin: (u16, u16)) -> Result<(), P4Error> {
-        for i in 0..4 {
-            let u = i + 1;
+    pub fn draw(&self, origin: (u16, u16), highlighted: &[usize]) -> Result<(), P4Error> {
+        for i in 0..self.depth as u16 {
+            queue!(
+                std::io::stdout(),
+                PrintAt(origin.0, i + 1 + origin.1, format!("{}", i).as_str()),
+            )
+            .map_err(|e| P4Error::OutputInterfaceError(e))?;
+        }
+        for i in 0..self.width as u16 {
             queue!(
                 std::io::stdout(),
-                PrintAt(origin.0, u + origin.1, format!("{}", i).as_str()),
                 PrintAt(
                     2 * (1 + i) + origin.0 * 2,
                     origin.1,
@@ -125,10 +162,16 @@ impl Plane {
             .map_err(|e| P4Error::OutputInterfaceError(e))?;
         }
         for (i, d) in self.data.iter().enumerate() {
-            let coord = (i as u16 % 4, i as u16 / 4);
+            let color = if highlighted.contains(&i) {
+                Plate::WIN_COLOR
+            } else {
+                d.color()
+            };
+            let width = self.width as u16;
+            let coord = (i as u16 % width, i as u16 / width);
             let x = origin.0 + coord.0 + 1;
             let y = origin.1 + coord.1 + 1;
-            queue!(std::io::stdout(), DrawSquareAt(x, y, d.color()))
+            queue!(std::io::stdout(), DrawSquareAt(x, y, color))
                 .map_err(|e| P4Error::OutputInterfaceError(e))?;
         }
         Ok(())
@@ -1,9 +1,8 @@
 use crate::utils::DrawSquareAtRawCoord;
 use crate::{
-    coord::Coord, coord::NumberCoord, error::P4Error, pilier::Pillar, pilier::Plane,
-    player::PlayerID,
+    coord::Coord, coord::NumberCoord, dimensions::Dimensions, error::P4Error, input::Input,
+    pilier::Pillar, pilier::Plane, player::PlayerID,
 };
-use core::iter::zip;
 use crossterm::{cursor::MoveTo, queue, style::Color, style::Print};
 use itertools::Itertools;
 
@@ -21,11 +20,18 @@ pub enum TypeOfDisplay {
 pub enum PreviewAction {
     Add,
     Remove,
+    /// Le coup conseillé au joueur
+    Hint,
 }
 
 #[derive(Clone)]
 pub struct Plate {
-    data: [Pillar; 16],
+    dims: Dimensions,
+    data: Vec<Pillar>,
+    /// Calculées une fois pour toutes à la création
+    lines: Arc<[Line]>,
+    /// Pour chaque case, les indices des lignes qui la traversent
+    lines_through: Arc<[Vec<usize>]>,
     pub type_of_display: Cell<TypeOfDisplay>,
 }
 
@@ -39,35 +45,99 @@ impl TypeOfDisplay {
 }
 
 impl Plate {
+    pub fn new(dims: Dimensions) -> Plate {
+        log::trace!("Empty plate created ({})", dims);
+        let lines = dims.lines();
+        let mut lines_through = vec![Vec::new(); dims.pillars() * dims.height() as usize];
+        for (i, line) in lines.iter().enumerate() {
+            for (x, y, h) in line {
+                lines_through[Self::cell_index(dims, (*x, *y, *h))].push(i);
+            }
+        }
+        Plate {
+            dims,
+            data: vec![Pillar::with_capacity(dims.height()); dims.pillars()],
+            lines: lines.into(),
+            lines_through: lines_through.into(),
+            type_of_display: TypeOfDisplay::Victor.into(),
+        }
+    }
+
+    pub fn dimensions(&self) -> Dimensions {
+        self.dims
+    }
+
     pub fn switch_view(&self) {
         log::info!("Switching view . . .");
         self.type_of_display
             .set(self.type_of_display.get().switch());
     }
     fn get_plane(&self, n: u8) -> Plane {
-        let mut res = [PlayerID::Empty; 16];
-        for i in 0..16 {
-            let temp = self.data[i].get_player(n.into()).clone();
-            res[i] = temp;
+        Plane {
+            data: self
+                .data
+                .iter()
+                .map(|pillar| pillar.get_player(n.into()))
+                .collect(),
+            width: self.dims.width(),
+            depth: self.dims.depth(),
         }
-        Plane { data: res }
+    }
+    fn index(&self, nco: &impl Coord) -> usize {
+        let (x, y) = nco.to_xy_coord();
+        self.dims.pillar_index(x as u8, y as u8)
     }
     fn get_coord_mut(&mut self, nco: impl Coord) -> &mut Pillar {
-        &mut self.data[nco.to_num() as usize]
+        let i = self.index(&nco);
+        &mut self.data[i]
     }
     fn get_coord(&self, nco: impl Coord) -> &Pillar {
-        &self.data[nco.to_num() as usize]
+        &self.data[self.index(&nco)]
     }
     fn get_pion(&self, nco: impl Coord, height: usize) -> PlayerID {
         self.get_coord(nco).get_player(height).clone()
     }
+    pub fn get_cell(&self, (x, y, h): (u8, u8, u8)) -> PlayerID {
+        self.data[self.dims.pillar_index(x, y)].get_player(h as usize)
+    }
+
+    /// Le pilier `nco` est-il sur le plateau ?
+    pub fn contains(&self, nco: &impl Coord) -> bool {
+        let (x, y) = nco.to_xy_coord();
+        self.dims.contains(x as u8, y as u8)
+    }
+
+    /// La hauteur où tomberait un jeton posé sur `nco`, `None` s'il ne peut pas y être posé
+    pub fn landing_height(&self, nco: &impl Coord) -> Option<u8> {
+        if !self.contains(nco) {
+            return None;
+        }
+        let pillar = &self.data[self.index(nco)];
+        (!pillar.is_full()).then(|| pillar.get_height())
+    }
 
     pub fn playable(&self, nco: impl Coord + Clone) -> bool {
-        !self.get_coord(nco.clone()).is_full()
+        self.landing_height(&nco).is_some()
+    }
+
+    /// Les coups possibles de A0 à la dernière colonne, avec la hauteur où tomberait le jeton
+    pub fn legal_moves(&self) -> impl Iterator<Item = (Input, u8)> + '_ {
+        self.data
+            .iter()
+            .enumerate()
+            .filter(|(_, pillar)| !pillar.is_full())
+            .map(|(i, pillar)| (Input::from(self.dims.pillar_xy(i)), pillar.get_height()))
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.legal_moves().next().is_none()
     }
 
     pub fn add_player(&mut self, nco: impl Coord + Clone, pl: PlayerID) -> Result<(), P4Error> {
         log::debug!("Adding player {:?} at {:?} [before]", pl, nco);
+        if !self.contains(&nco) {
+            return Err(P4Error::OutOfPlate(nco.as_letter_coord()));
+        }
         let pillar = self.get_coord_mut(nco.clone());
         if pillar.is_full() {
             let nco_lc = nco.as_letter_coord();
@@ -83,11 +153,25 @@ impl Plate {
             t => t,
         }
     }
+
+    /// Retire le dernier jeton posé sur `nco` et renvoie son joueur
+    pub fn undo(&mut self, nco: impl Coord + Clone) -> Result<PlayerID, P4Error> {
+        log::debug!("Removing top of {:?}", nco);
+        if !self.contains(&nco) {
+            return Err(P4Error::OutOfPlate(nco.as_letter_coord()));
+        }
+        self.get_coord_mut(nco.clone()).pop().map_err(|e| match e {
+            P4Error::EmptyPillar(None) => P4Error::EmptyPillar(Some(nco.as_letter_coord())),
+            e => e,
+        })
+    }
 }
 
 /// DISPLAYING
 impl Plate {
     const PREVIEW_COLOR: Color = Color::AnsiValue(1);
+    const HINT_COLOR: Color = Color::AnsiValue(226);
+    pub const WIN_COLOR: Color = Color::AnsiValue(40);
     const ORIGIN: (u16, u16) = (0, 0);
     pub fn plot(&self) {
         match self.type_of_display.get() {
@@ -96,11 +180,34 @@ impl Plate {
         };
     }
 
+    /// Lignes occupées par un étage dans la vue de Victor
+    fn plane_rows(&self) -> u16 {
+        self.dims.depth() as u16 + 2
+    }
+
+    /// Lignes occupées par un pilier dans la vue d'Arthur
+    fn pillar_rows(&self) -> u16 {
+        self.dims.height() as u16 + 2
+    }
+
+    /// Colonnes occupées par la plus large des deux vues
+    pub fn plot_width(&self) -> u16 {
+        let width = self.dims.width() as u16;
+        (2 * width + 4).max(3 * width)
+    }
+
     pub fn clear_plot(&self) {
         let mut stdout = io::stdout();
         crossterm::execute!(stdout, SavePosition).unwrap();
-        for y in 0..(4 * 6) {
-            queue!(stdout, MoveTo(0, y), Print("  ".repeat(7))).unwrap();
+        let (height, depth) = (self.dims.height() as u16, self.dims.depth() as u16);
+        let rows = (height * self.plane_rows()).max(depth * self.pillar_rows());
+        for y in 0..rows {
+            queue!(
+                stdout,
+                MoveTo(0, y),
+                Print(" ".repeat(self.plot_width().max(14) as usize))
+            )
+            .unwrap();
         }
         crossterm::execute!(stdout, RestorePosition).unwrap();
     }
@@ -111,25 +218,31 @@ impl Plate {
         action: PreviewAction,
     ) -> Result<(), P4Error> {
         log::trace!("Updating preview");
-        let height: u16 = self.get_coord(coords_preview.clone()).get_height().clone() as u16;
-        // if the pillar is filled
-        if height >= 4 {
-            return Err(P4Error::OverFilledPillar(Some(
-                coords_preview.clone().as_letter_coord(),
-            )));
-        }
-        let shared_height = Arc::new(height);
+        let height: u16 = match self.landing_height(&coords_preview) {
+            Some(h) => h as u16,
+            None if !self.contains(&coords_preview) => {
+                return Err(P4Error::OutOfPlate(coords_preview.as_letter_coord()))
+            }
+            // if the pillar is filled
+            None => {
+                return Err(P4Error::OverFilledPillar(Some(
+                    coords_preview.clone().as_letter_coord(),
+                )))
+            }
+        };
+        let shared_height = Arc::new(self.dims.height() as u16 - 1 - height);
+        let (plane_rows, pillar_rows) = (self.plane_rows(), self.pillar_rows());
         let coord_process_closure: Box<dyn FnOnce(u16, u16) -> (u16, u16)> = match self
             .type_of_display
             .get()
         {
             TypeOfDisplay::Arthur => {
                 let shared_height_clone = shared_height.clone();
-                Box::new(move |x, y| (x as u16 * 3, y as u16 * 6 + (3 - *shared_height_clone)))
+                Box::new(move |x, y| (x as u16 * 3, y as u16 * pillar_rows + *shared_height_clone))
             }
             TypeOfDisplay::Victor => {
                 let shared_height_clone = shared_height.clone();
-                Box::new(move |x, y| (x as u16 * 2 + 2, y as u16 + 6 * (3 - *shared_height_clone)))
+                Box::new(move |x, y| (x as u16 * 2 + 2, y as u16 + plane_rows * *shared_height_clone))
             } // TypeOfDisplay::Both => {
               //     let shared_height_clone = shared_height.clone();
               //     Box::new(move |x, y| (x as u16 * 2 + 2, y as u16 + 6 * (3 - *shared_height_clone)))
@@ -143,6 +256,7 @@ impl Plate {
         let y: u16 = org_y + 1 + y_prev as u16;
         let color = match action {
             PreviewAction::Add => Self::PREVIEW_COLOR,
+            PreviewAction::Hint => Self::HINT_COLOR,
             PreviewAction::Remove => self.get_pion(coords_preview, height as usize).color(),
         };
         queue!(
@@ -166,13 +280,21 @@ impl Plate {
         let mut w = io::stdout();
         crossterm::execute!(w, SavePosition).unwrap();
         let (org_x, org_y) = Self::ORIGIN;
-        let plane_y_size = 6u16;
+        let plane_y_size = self.plane_rows();
+        let line = self.winning_line().map(|(_, line)| line);
+        let top = self.dims.height() - 1;
 
-        for height in 0..4 {
+        for height in 0..=top {
             let plane = self.get_plane(height);
-            let norm_origin = (0, plane_y_size * (3 - height) as u16);
+            let highlighted: Vec<usize> = line
+                .iter()
+                .flatten()
+                .filter(|(_, _, h)| *h == height)
+                .map(|(x, y, _)| self.dims.pillar_index(*x, *y))
+                .collect();
+            let norm_origin = (0, plane_y_size * (top - height) as u16);
             let plate_origin: (u16, u16) = (norm_origin.0 + org_x, norm_origin.1 + org_y);
-            plane.draw(plate_origin)?;
+            plane.draw(plate_origin, &highlighted)?;
         }
         crossterm::execute!(w, RestorePosition).unwrap();
         w.flush().unwrap();
@@ -185,15 +307,22 @@ impl Plate {
         let mut w = io::stdout();
         crossterm::execute!(w, SavePosition).unwrap();
         let (org_x, org_y) = Self::ORIGIN;
-        let (pillar_width, pillar_height) = (1, 6);
+        let (pillar_width, pillar_height) = (1, self.pillar_rows());
         let separation_width = 2;
+        let line = self.winning_line().map(|(_, line)| line);
 
         for (i, pillar) in self.data.iter().enumerate() {
-            let pillar_name = NumberCoord::new(i as u8).as_letter_coord();
+            let pillar_name = NumberCoord::with_dimensions(i as u8, self.dims).as_letter_coord();
             let (i_x, i_y) = pillar_name.to_xy_coord();
+            let highlighted: Vec<usize> = line
+                .iter()
+                .flatten()
+                .filter(|(x, y, _)| (*x as u16, *y as u16) == (i_x, i_y))
+                .map(|(_, _, h)| *h as usize)
+                .collect();
             let norm_origin = (i_x * (pillar_width + separation_width), i_y * pillar_height);
             let pillar_origin: (u16, u16) = (norm_origin.0 + org_x, norm_origin.1 + org_y);
-            pillar.draw(pillar_origin, pillar_name)?;
+            pillar.draw(pillar_origin, pillar_name, &highlighted)?;
         }
         crossterm::execute!(w, RestorePosition).unwrap();
         w.flush().unwrap();
@@ -202,93 +331,78 @@ impl Plate {
     }
 }
 
+/// (x, y, hauteur) des cases alignées
+pub type Line = Vec<(u8, u8, u8)>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    Ongoing,
+    Win(PlayerID, Line),
+    Draw,
+}
+
 // CHECKIING WIN
 impl Plate {
-    const WIN_COMBINATIONS: [[usize; 4]; 6] = {
-        let diag1 = [0, 1, 2, 3];
-        let diag2 = [3, 2, 1, 0];
-        let h0 = [0, 0, 0, 0];
-        let h1 = [1, 1, 1, 1];
-        let h2 = [2, 2, 2, 2];
-        let h3 = [3, 3, 3, 3];
-        [diag1, diag2, h0, h1, h2, h3]
-    };
-
-    fn get_line_x_of_pillar<'a>(&'a self, x: usize) -> Box<impl Iterator<Item = &Pillar>> {
-        Box::new(self.data.iter().skip(4 * x).take(4))
-    }
-    fn get_line_y_of_pillar(&self, y: usize) -> Box<impl Iterator<Item = &Pillar>> {
-        Box::new(self.data.iter().skip(y).step_by(4))
-    }
-    fn get_first_diagonal(&self) -> Box<impl Iterator<Item = &Pillar>> {
-        Box::new(self.data.iter().skip(0).step_by(5))
-    }
-    fn get_second_diagonal(&self) -> Box<impl Iterator<Item = &Pillar>> {
-        Box::new(self.data.iter().skip(3).step_by(3))
-    }
-
-    fn check_4_origin<'a, F>(&'a self, get_pillar_line: F) -> bool
-    where
-        F: Fn(usize) -> Box<dyn Iterator<Item = &'a Pillar> + 'a>,
-    {
-        let mut res: bool = false;
-        for i in 0..4 {
-            for line_win in Self::WIN_COMBINATIONS {
-                let line_win_iter = line_win.into_iter();
-                res |= all_elements_identical(
-                    zip(get_pillar_line(i), line_win_iter).map(|(p, h)| p.get_player(h)),
-                );
-            }
-        }
-        res
-    }
-
-    fn check_diag<'a, F>(&'a self, get_pillar_diag: F) -> bool
-    where
-        F: Fn() -> Box<dyn Iterator<Item = &'a Pillar> + 'a>,
-    {
-        let mut res: bool = false;
-        for line_win in Self::WIN_COMBINATIONS {
-            let line_win_iter = line_win.into_iter();
-            res |= all_elements_identical(
-                zip(get_pillar_diag(), line_win_iter).map(|(p, h)| p.get_player(h)),
-            );
-        }
-        res
+    /// Les lignes gagnantes du plateau, 76 sur le cube standard
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
     }
 
-    pub fn check_win(&self) -> bool {
-        //log::debug!("{}", self.data.iter().any(|p| p.is_full()));
-        //log::debug!("{:?}", all_elements_identical((0..4).map(|i| plate[i].get_player(0))));
+    fn cell_index(dims: Dimensions, (x, y, h): (u8, u8, u8)) -> usize {
+        dims.pillar_index(x, y) * dims.height() as usize + h as usize
+    }
 
-        let plate = &self.data;
-        if plate
+    /// Les lignes qui passent par la case `cell`
+    pub fn lines_through(&self, cell: (u8, u8, u8)) -> impl Iterator<Item = &Line> + '_ {
+        self.lines_through[Self::cell_index(self.dims, cell)]
             .iter()
-            .any(|p| all_elements_identical(p.data.into_iter()))
-        {
-            return true;
-        }
+            .map(|i| &self.lines[*i])
+    }
 
-        if self.check_4_origin(|i| self.get_line_x_of_pillar(i))
-            || self.check_4_origin(|i| self.get_line_y_of_pillar(i))
-            || self.check_diag(|| self.get_first_diagonal())
-            || self.check_diag(|| self.get_second_diagonal())
-        {
-            return true;
+    /// Un jeton de `player` posé sur `nco` compléterait-il une ligne ?
+    pub fn completes_line(&self, nco: &impl Coord, player: PlayerID) -> bool {
+        let Some(h) = self.landing_height(nco) else {
+            return false;
+        };
+        let (x, y) = nco.to_xy_coord();
+        let cell = (x as u8, y as u8, h);
+        self.lines_through(cell)
+            .any(|line| line.iter().all(|c| *c == cell || self.get_cell(*c) == player))
+    }
+
+    fn line_owner(&self, line: &Line) -> Option<PlayerID> {
+        let player_at = |c: &(u8, u8, u8)| self.get_cell(*c);
+        all_elements_identical(line.iter().map(player_at)).then(|| player_at(&line[0]))
+    }
+
+    /// Le gagnant et les cases de sa ligne, s'il y en a un
+    pub fn winning_line(&self) -> Option<(PlayerID, Line)> {
+        self.lines
+            .iter()
+            .find_map(|line| self.line_owner(line).map(|p| (p, line.clone())))
+    }
+
+    pub fn outcome(&self) -> Outcome {
+        match self.winning_line() {
+            Some((winner, line)) => Outcome::Win(winner, line),
+            None if self.is_full() => Outcome::Draw,
+            None => Outcome::Ongoing,
         }
+    }
 
-        return false;
+    pub fn check_win(&self) -> bool {
+        self.winning_line().is_some()
     }
 }
 
 impl std::fmt::Debug for Plate {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "{:?}", self.type_of_display)?;
-        for (i, p) in self.data.into_iter().enumerate() {
-            if i % 4 == 0 && i != 0 {
+        for (i, p) in self.data.iter().enumerate() {
+            if i % self.dims.width() as usize == 0 && i != 0 {
                 writeln!(f)?;
             }
-            let coords = NumberCoord::new(i as u8).as_letter_coord();
+            let coords = NumberCoord::with_dimensions(i as u8, self.dims).as_letter_coord();
             write!(f, "{:?} : {},", coords, p)?;
         }
         Ok(())
@@ -297,11 +411,7 @@ impl std::fmt::Debug for Plate {
 
 impl Default for Plate {
     fn default() -> Self {
-        log::trace!("Empty plate created");
-        Plate {
-            data: [Pillar::default(); 16],
-            type_of_display: TypeOfDisplay::Victor.into(),
-        }
+        Plate::new(Dimensions::STANDARD)
     }
 }
 
@@ -313,3 +423,108 @@ where
         .map(|(one, two)| one == two && one != PlayerID::Empty)
         .all(|e| e)
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Un cube plein sans aucune ligne, 32 jetons à chacun
+    pub(crate) const DRAWN_PILLARS: [[u8; 4]; 16] = [
+        [1, 0, 0, 0],
+        [1, 0, 1, 1],
+        [1, 1, 1, 0],
+        [0, 0, 1, 1],
+        [0, 0, 0, 1],
+        [0, 1, 1, 0],
+        [0, 0, 0, 1],
+        [1, 1, 1, 0],
+        [0, 0, 1, 1],
+        [1, 0, 0, 0],
+        [0, 1, 0, 1],
+        [0, 1, 0, 1],
+        [1, 1, 0, 1],
+        [1, 0, 0, 0],
+        [1, 0, 0, 1],
+        [0, 1, 1, 1],
+    ];
+
+    pub(crate) fn filled(pillars: [[u8; 4]; 16]) -> Plate {
+        let mut plt = Plate::default();
+        for (i, pillar) in pillars.iter().enumerate() {
+            for p in pillar {
+                let pl = if *p == 0 { PlayerID::P1 } else { PlayerID::P2 };
+                plt.add_player(NumberCoord::new(i as u8), pl).unwrap();
+            }
+        }
+        plt
+    }
+
+    #[test]
+    fn there_are_76_distinct_lines() {
+        let mut lines: Vec<Line> = Plate::default().lines().to_vec();
+        lines.iter_mut().for_each(|l| l.sort());
+        lines.sort();
+        lines.dedup();
+        assert_eq!(lines.len(), 76);
+    }
+
+    #[test]
+    fn legal_moves_land_on_top() {
+        let mut plt = Plate::new("3x2x2:2".parse().unwrap());
+        assert_eq!(plt.legal_moves().count(), 6);
+        plt.add_player(Input::from((1, 1)), PlayerID::P1).unwrap();
+        plt.add_player(Input::from((1, 1)), PlayerID::P2).unwrap();
+        plt.add_player(Input::from((2, 0)), PlayerID::P1).unwrap();
+        let moves: Vec<(String, u8)> = plt
+            .legal_moves()
+            .map(|(mv, h)| (mv.to_string(), h))
+            .collect();
+        let expected = [("A0", 0), ("B0", 0), ("C0", 1), ("A1", 0), ("C1", 0)];
+        assert_eq!(moves, expected.map(|(mv, h)| (mv.to_string(), h)));
+        assert_eq!(plt.landing_height(&Input::from((2, 0))), Some(1));
+        assert_eq!(plt.landing_height(&Input::from((1, 1))), None);
+        assert_eq!(plt.landing_height(&Input::from((3, 0))), None);
+        assert!(!plt.is_full());
+        assert!(filled(DRAWN_PILLARS).is_full());
+    }
+
+    #[test]
+    fn completes_line_looks_ahead() {
+        let mut plt = Plate::default();
+        for x in 0..3 {
+            plt.add_player(Input::from((x, 0)), PlayerID::P1).unwrap();
+        }
+        assert!(plt.completes_line(&Input::from((3, 0)), PlayerID::P1));
+        assert!(!plt.completes_line(&Input::from((3, 0)), PlayerID::P2));
+        assert!(!plt.completes_line(&Input::from((3, 1)), PlayerID::P1));
+        // le jeton ne tomberait plus au niveau de la ligne
+        plt.add_player(Input::from((3, 0)), PlayerID::P2).unwrap();
+        assert!(!plt.completes_line(&Input::from((3, 0)), PlayerID::P1));
+        assert_eq!(plt.outcome(), Outcome::Ongoing);
+    }
+
+    #[test]
+    fn empty_plate_is_ongoing() {
+        assert_eq!(Plate::default().outcome(), Outcome::Ongoing);
+    }
+
+    #[test]
+    fn full_plate_without_line_is_a_draw() {
+        let plt = filled(DRAWN_PILLARS);
+        assert!(!plt.check_win());
+        assert_eq!(plt.outcome(), Outcome::Draw);
+    }
+
+    #[test]
+    fn full_plate_with_line_is_a_win() {
+        let mut pillars = DRAWN_PILLARS;
+        // la diagonale A0 -> D3 au premier étage
+        for i in [0, 5, 10, 15] {
+            pillars[i][1] = 1;
+        }
+        assert_eq!(
+            filled(pillars).outcome(),
+            Outcome::Win(PlayerID::P2, vec![(0, 0, 1), (1, 1, 1), (2, 2, 1), (3, 3, 1)])
+        );
+    }
+}
@@ -5,6 +5,8 @@ use crossterm::{style::Color, Command};
 pub enum PlayerID {
     P1,
     P2,
+    P3,
+    P4,
     Empty,
 }
 
@@ -18,25 +20,100 @@ impl PlayerID {
     const VOID_COLOR: Color = Color::AnsiValue(236);
     const PLAYER1_COLOR: Color = Color::AnsiValue(223);
     const PLAYER2_COLOR: Color = Color::AnsiValue(130);
+    const PLAYER3_COLOR: Color = Color::AnsiValue(75);
+    const PLAYER4_COLOR: Color = Color::AnsiValue(176);
+    const ALL: [PlayerID; TurnOrder::MAX_PLAYERS as usize] =
+        [PlayerID::P1, PlayerID::P2, PlayerID::P3, PlayerID::P4];
 
     pub fn color(&self) -> Color {
         match self {
             PlayerID::Empty => Self::VOID_COLOR,
             PlayerID::P1 => Self::PLAYER1_COLOR,
             PlayerID::P2 => Self::PLAYER2_COLOR,
+            PlayerID::P3 => Self::PLAYER3_COLOR,
+            PlayerID::P4 => Self::PLAYER4_COLOR,
         }
     }
-    pub fn joue(&mut self) {
-        *self = match self {
+
+    /// La place dans l'ordre de jeu, à partir de 0
+    pub fn index(&self) -> usize {
+        match self {
             PlayerID::Empty => panic!("{:?}", P4Error::EmptyPlayerPlayed),
-            PlayerID::P1 => PlayerID::P2,
-            PlayerID::P2 => PlayerID::P1,
+            p => *p as usize,
+        }
+    }
+
+    pub fn from_index(i: usize) -> PlayerID {
+        Self::ALL[i]
+    }
+}
+
+/// L'ordre de jeu : `P1` commence, puis chacun son tour jusqu'au dernier joueur
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TurnOrder {
+    count: u8,
+}
+
+impl Default for TurnOrder {
+    fn default() -> Self {
+        TurnOrder { count: 2 }
+    }
+}
+
+impl TurnOrder {
+    pub const MAX_PLAYERS: u8 = 4;
+
+    pub fn new(count: u8) -> Result<TurnOrder, P4Error> {
+        if !(2..=Self::MAX_PLAYERS).contains(&count) {
+            return Err(P4Error::InvalidArgument(format!(
+                "a game needs between 2 and {} players, not {}",
+                Self::MAX_PLAYERS,
+                count
+            )));
         }
+        Ok(TurnOrder { count })
+    }
+
+    pub fn count(&self) -> u8 {
+        self.count
+    }
+
+    pub fn first(&self) -> PlayerID {
+        PlayerID::P1
+    }
+
+    pub fn players(&self) -> impl Iterator<Item = PlayerID> {
+        PlayerID::ALL.into_iter().take(self.count as usize)
+    }
+
+    pub fn next(&self, player: PlayerID) -> PlayerID {
+        PlayerID::from_index((player.index() + 1) % self.count as usize)
+    }
+
+    pub fn previous(&self, player: PlayerID) -> PlayerID {
+        let count = self.count as usize;
+        PlayerID::from_index((player.index() + count - 1) % count)
     }
 }
+
 impl std::fmt::Display for PlayerID {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         DrawSquare(self.color()).write_ansi(f)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turns_go_round() {
+        let order = TurnOrder::new(3).unwrap();
+        assert_eq!(order.next(PlayerID::P2), PlayerID::P3);
+        assert_eq!(order.next(PlayerID::P3), PlayerID::P1);
+        assert_eq!(order.previous(PlayerID::P1), PlayerID::P3);
+        assert_eq!(TurnOrder::default().next(PlayerID::P2), PlayerID::P1);
+        assert!(TurnOrder::new(1).is_err() && TurnOrder::new(5).is_err());
+    }
+}
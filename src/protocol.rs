@@ -0,0 +1,550 @@
+//! Protocole texte des moteurs externes, à la manière d'UCI aux échecs.
+//! Le programme qui arbitre écrit sur l'entrée standard du moteur, une commande par ligne :
+//!
+//! - `p4i` : le moteur se présente par `id name <nom>` puis répond `p4iok`
+//! - `isready` : le moteur répond `readyok` quand il est prêt
+//! - `position [size <LxNxH:A>] [players <N>] [moves <coups>...]` : la partie
+//!   jouée jusqu'ici, sur le cube standard à deux par défaut
+//! - `go [movetime <ms>]` : le moteur cherche au plus `ms` millisecondes puis
+//!   répond `bestmove <coup>`, ou `bestmove none` s'il ne peut pas jouer
+//! - `quit` : le moteur s'arrête
+//!
+//! Le moteur peut aussi écrire des lignes `info <texte>`, qui sont ignorées,
+//! comme toute commande inconnue.
+use std::{
+    cell::RefCell,
+    io::{BufRead, BufReader, Write},
+    process::{self, Child, ChildStdin, Stdio},
+    str::FromStr,
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config::PlayerKind,
+    dimensions::Dimensions,
+    engine::Engine,
+    error::P4Error,
+    input::Input,
+    logic::Thinker,
+    mcts::Budget,
+    notation::{parse_notation, to_notation},
+    plate::Plate,
+    player::TurnOrder,
+};
+
+/// Ce que l'arbitre envoie au moteur
+#[derive(Clone, Debug, PartialEq)]
+pub enum EngineCommand {
+    Hello,
+    IsReady,
+    Position {
+        dims: Dimensions,
+        players: TurnOrder,
+        moves: Vec<Input>,
+    },
+    Go {
+        movetime: Option<Duration>,
+    },
+    Quit,
+}
+
+/// Ce que le moteur répond
+#[derive(Clone, Debug, PartialEq)]
+pub enum EngineReply {
+    Id(String),
+    HelloOk,
+    ReadyOk,
+    BestMove(Option<Input>),
+    Info(String),
+}
+
+impl std::fmt::Display for EngineCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EngineCommand::Hello => write!(f, "p4i"),
+            EngineCommand::IsReady => write!(f, "isready"),
+            EngineCommand::Position {
+                dims,
+                players,
+                moves,
+            } => {
+                write!(f, "position size {} players {}", dims, players.count())?;
+                if !moves.is_empty() {
+                    write!(f, " moves {}", to_notation(moves))?;
+                }
+                Ok(())
+            }
+            EngineCommand::Go { movetime: None } => write!(f, "go"),
+            EngineCommand::Go {
+                movetime: Some(time),
+            } => write!(f, "go movetime {}", time.as_millis()),
+            EngineCommand::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+impl FromStr for EngineCommand {
+    type Err = P4Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || P4Error::ProtocolError(s.to_owned());
+        let mut words = s.split_whitespace();
+        Ok(match words.next().unwrap_or_default() {
+            "p4i" => EngineCommand::Hello,
+            "isready" => EngineCommand::IsReady,
+            "position" => {
+                let mut dims = Dimensions::STANDARD;
+                let mut players = TurnOrder::default();
+                let mut moves = Vec::new();
+                while let Some(word) = words.next() {
+                    match (word, words.next()) {
+                        ("size", Some(size)) => dims = size.parse()?,
+                        ("players", Some(n)) => {
+                            players = TurnOrder::new(n.parse().map_err(|_| err())?)?
+                        }
+                        ("moves", first) => {
+                            let rest: Vec<&str> = first.into_iter().chain(words.by_ref()).collect();
+                            moves = parse_notation(&rest.join(" "))?;
+                        }
+                        _ => return Err(err()),
+                    }
+                }
+                EngineCommand::Position {
+                    dims,
+                    players,
+                    moves,
+                }
+            }
+            "go" => match (words.next(), words.next()) {
+                (None, _) => EngineCommand::Go { movetime: None },
+                (Some("movetime"), Some(ms)) => EngineCommand::Go {
+                    movetime: Some(Duration::from_millis(ms.parse().map_err(|_| err())?)),
+                },
+                _ => return Err(err()),
+            },
+            "quit" => EngineCommand::Quit,
+            _ => return Err(err()),
+        })
+    }
+}
+
+impl std::fmt::Display for EngineReply {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EngineReply::Id(name) => write!(f, "id name {}", name),
+            EngineReply::HelloOk => write!(f, "p4iok"),
+            EngineReply::ReadyOk => write!(f, "readyok"),
+            EngineReply::BestMove(Some(mv)) => write!(f, "bestmove {}", mv),
+            EngineReply::BestMove(None) => write!(f, "bestmove none"),
+            EngineReply::Info(text) => write!(f, "info {}", text),
+        }
+    }
+}
+
+impl FromStr for EngineReply {
+    type Err = P4Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || P4Error::ProtocolError(s.to_owned());
+        let (word, rest) = s.split_once(' ').unwrap_or((s, ""));
+        Ok(match word {
+            "id" => EngineReply::Id(rest.strip_prefix("name ").ok_or_else(err)?.to_owned()),
+            "p4iok" => EngineReply::HelloOk,
+            "readyok" => EngineReply::ReadyOk,
+            "bestmove" if rest == "none" => EngineReply::BestMove(None),
+            "bestmove" => match parse_notation(rest)?.as_slice() {
+                [mv] => EngineReply::BestMove(Some(mv.clone())),
+                _ => return Err(err()),
+            },
+            "info" => EngineReply::Info(rest.to_owned()),
+            _ => return Err(err()),
+        })
+    }
+}
+
+/// Les coups mènent-ils bien de l'ouverture à `plt` ?
+fn leads_to(moves: &[Input], plt: &Plate, players: TurnOrder) -> bool {
+    let dims = plt.dimensions();
+    let mut engine = Engine::with_players(dims, players);
+    if moves.iter().any(|mv| engine.play(mv.clone()).is_err()) {
+        return false;
+    }
+    (0..dims.pillars()).all(|i| {
+        let (x, y) = dims.pillar_xy(i);
+        (0..dims.height()).all(|h| engine.plate().get_cell((x, y, h)) == plt.get_cell((x, y, h)))
+    })
+}
+
+/// Un moteur externe, lancé comme processus fils et interrogé par le protocole
+pub struct ProcessThinker {
+    child: Child,
+    stdin: RefCell<ChildStdin>,
+    lines: Receiver<String>,
+    name: String,
+    players: TurnOrder,
+    time_budget: Duration,
+}
+
+impl ProcessThinker {
+    /// Le temps laissé au moteur pour se présenter et, en plus de `time_budget`, pour répondre
+    const GRACE: Duration = Duration::from_secs(5);
+
+    pub fn new(
+        command: &[String],
+        players: TurnOrder,
+        time_budget: Duration,
+    ) -> Result<ProcessThinker, P4Error> {
+        let (program, args) = command
+            .split_first()
+            .ok_or_else(|| P4Error::EngineError(String::from("no command to run")))?;
+        let mut child = process::Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| P4Error::EngineError(format!("cannot run {} : {}", program, e)))?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        // lu à part pour pouvoir attendre une réponse avec une limite de temps
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut thinker = ProcessThinker {
+            child,
+            stdin: RefCell::new(stdin),
+            lines,
+            name: program.clone(),
+            players,
+            time_budget,
+        };
+        thinker.send(&EngineCommand::Hello)?;
+        loop {
+            match thinker.recv(Self::GRACE)? {
+                EngineReply::Id(name) => thinker.name = name,
+                EngineReply::HelloOk => break,
+                _ => {}
+            }
+        }
+        log::info!("External engine {} is ready", thinker.name);
+        Ok(thinker)
+    }
+
+    fn send(&self, command: &EngineCommand) -> Result<(), P4Error> {
+        log::debug!("To {} : {}", self.name, command);
+        writeln!(self.stdin.borrow_mut(), "{}", command)
+            .map_err(|e| P4Error::EngineError(format!("{} stopped listening : {}", self.name, e)))
+    }
+
+    /// La prochaine réponse comprise, les lignes inconnues sont ignorées
+    fn recv(&self, timeout: Duration) -> Result<EngineReply, P4Error> {
+        loop {
+            let line = match self.lines.recv_timeout(timeout) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(P4Error::EngineError(format!(
+                        "{} did not answer within {} ms",
+                        self.name,
+                        timeout.as_millis()
+                    )))
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(P4Error::EngineError(format!("{} has stopped", self.name)))
+                }
+            };
+            log::debug!("From {} : {}", self.name, line);
+            match line.parse() {
+                Ok(reply) => return Ok(reply),
+                Err(_) => log::warn!("Ignoring {:?} from {}", line, self.name),
+            }
+        }
+    }
+}
+
+impl Thinker for ProcessThinker {
+    /// Sans les coups de la partie, seule l'ouverture peut être envoyée
+    fn think(&self, plt: &Plate) -> Result<Input, P4Error> {
+        self.think_after(plt, &[])
+    }
+
+    fn think_after(&self, plt: &Plate, history: &[Input]) -> Result<Input, P4Error> {
+        if !leads_to(history, plt, self.players) {
+            return Err(P4Error::EngineError(String::from(
+                "the moves sent do not lead to this plate",
+            )));
+        }
+        self.send(&EngineCommand::Position {
+            dims: plt.dimensions(),
+            players: self.players,
+            moves: history.to_vec(),
+        })?;
+        self.send(&EngineCommand::Go {
+            movetime: Some(self.time_budget),
+        })?;
+        loop {
+            match self.recv(self.time_budget + Self::GRACE)? {
+                EngineReply::BestMove(Some(mv)) if !plt.playable(mv.clone()) => {
+                    return Err(P4Error::EngineError(format!(
+                        "{} played {}, which is not a legal move",
+                        self.name, mv
+                    )))
+                }
+                EngineReply::BestMove(Some(mv)) => return Ok(mv),
+                EngineReply::BestMove(None) => return Err(P4Error::NoLegalMove),
+                EngineReply::Info(text) => log::info!("{} : {}", self.name, text),
+                _ => {}
+            }
+        }
+    }
+
+    fn name(&self) -> Option<&str> {
+        Some(&self.name)
+    }
+}
+
+impl Drop for ProcessThinker {
+    /// Le moteur a `GRACE` pour obéir à `quit`, il est tué ensuite
+    fn drop(&mut self) {
+        let deadline = Instant::now() + Self::GRACE;
+        if self.send(&EngineCommand::Quit).is_ok() {
+            while Instant::now() < deadline {
+                match self.child.try_wait() {
+                    Ok(None) => thread::sleep(Duration::from_millis(10)),
+                    Ok(Some(_)) => return,
+                    Err(_) => break,
+                }
+            }
+        }
+        log::warn!("Killing {}", self.name);
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Expose un joueur intégré par le protocole, sur l'entrée et la sortie données
+pub struct EngineAdapter {
+    kind: PlayerKind,
+    seed: u64,
+}
+
+impl EngineAdapter {
+    pub fn new(kind: PlayerKind, seed: u64) -> Result<EngineAdapter, P4Error> {
+        if kind == PlayerKind::Human {
+            return Err(P4Error::InvalidArgument(String::from(
+                "a human cannot answer the engine protocol",
+            )));
+        }
+        Ok(EngineAdapter { kind, seed })
+    }
+
+    /// Répond aux commandes jusqu'à `quit` ou la fin de l'entrée
+    pub fn run(&self, input: impl BufRead, mut output: impl Write) -> Result<(), P4Error> {
+        let mut engine = Engine::new();
+        let mut reply = |reply: EngineReply| -> Result<(), P4Error> {
+            writeln!(output, "{}", reply)?;
+            output.flush()?;
+            Ok(())
+        };
+        for line in input.lines() {
+            let line = line?;
+            let command = match line.parse() {
+                Ok(command) => command,
+                Err(e) => {
+                    log::warn!("{}", e);
+                    continue;
+                }
+            };
+            match command {
+                EngineCommand::Hello => {
+                    reply(EngineReply::Id(format!("puissance {}", self.kind)))?;
+                    reply(EngineReply::HelloOk)?;
+                }
+                EngineCommand::IsReady => reply(EngineReply::ReadyOk)?,
+                EngineCommand::Position {
+                    dims,
+                    players,
+                    moves,
+                } => {
+                    engine = Engine::with_players(dims, players);
+                    for mv in moves {
+                        if let Err(e) = engine.play(mv) {
+                            reply(EngineReply::Info(format!("invalid position : {}", e)))?;
+                            break;
+                        }
+                    }
+                }
+                EngineCommand::Go { movetime } => {
+                    let mv = self.best_move(&engine, movetime);
+                    if let Err(e) = &mv {
+                        reply(EngineReply::Info(e.to_string()))?;
+                    }
+                    reply(EngineReply::BestMove(mv.ok()))?;
+                }
+                EngineCommand::Quit => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn best_move(&self, engine: &Engine, movetime: Option<Duration>) -> Result<Input, P4Error> {
+        if engine.legal_moves().is_empty() {
+            return Err(P4Error::NoLegalMove);
+        }
+        let kind = within(&self.kind, movetime);
+        let seed = self.seed.wrapping_add(engine.history().len() as u64);
+        let thinker = kind.build(engine.current_player(), engine.turn_order(), seed, None)?;
+        engine.ask(thinker.as_ref())
+    }
+}
+
+/// Le joueur dans le temps accordé par `go movetime`, pour ceux qui comptent leur temps
+fn within(kind: &PlayerKind, movetime: Option<Duration>) -> PlayerKind {
+    match (kind, movetime) {
+        (PlayerKind::Negamax { depth, .. }, Some(time_budget)) => PlayerKind::Negamax {
+            depth: *depth,
+            time_budget,
+        },
+        (PlayerKind::Mcts { playout, .. }, Some(time)) => PlayerKind::Mcts {
+            budget: Budget::Time(time),
+            playout: *playout,
+        },
+        (kind, _) => kind.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn commands_round_trip() {
+        for line in [
+            "p4i",
+            "isready",
+            "position size 4x4x4:4 players 2",
+            "position size 5x5x5:4 players 3 moves A0 B2 E4",
+            "go",
+            "go movetime 250",
+            "quit",
+        ] {
+            assert_eq!(line.parse::<EngineCommand>().unwrap().to_string(), line);
+        }
+        assert_eq!(
+            "position moves A0 B1".parse::<EngineCommand>().unwrap(),
+            EngineCommand::Position {
+                dims: Dimensions::STANDARD,
+                players: TurnOrder::default(),
+                moves: parse_notation("A0 B1").unwrap(),
+            }
+        );
+        for line in [
+            "id name Deep Cube",
+            "p4iok",
+            "readyok",
+            "bestmove A2",
+            "bestmove none",
+        ] {
+            assert_eq!(line.parse::<EngineReply>().unwrap().to_string(), line);
+        }
+        for bad in ["go movetime", "position size", "bestmove A0 A1", "uci"] {
+            assert!(bad.parse::<EngineCommand>().is_err() && bad.parse::<EngineReply>().is_err());
+        }
+    }
+
+    #[test]
+    fn adapter_answers_go() {
+        let adapter = EngineAdapter::new("negamax:2".parse().unwrap(), 0).unwrap();
+        let input = "p4i\nisready\nposition moves A0 B0 A0 B0 A0 B0\ngo movetime 500\nquit\ngo\n";
+        let mut output = Vec::new();
+        adapter.run(input.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "id name puissance negamax:2:2000",
+                "p4iok",
+                "readyok",
+                "bestmove A0"
+            ]
+        );
+    }
+
+    #[test]
+    fn movetime_bounds_searching_players() {
+        let time = Duration::from_millis(300);
+        for (kind, timed) in [
+            ("negamax:4", "negamax:4:300"),
+            ("mcts:5000", "mcts:300ms"),
+            ("mcts:1000ms:random", "mcts:300ms:random"),
+            ("random", "random"),
+        ] {
+            let kind: PlayerKind = kind.parse().unwrap();
+            assert_eq!(within(&kind, Some(time)), timed.parse().unwrap());
+            assert_eq!(within(&kind, None), kind);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn process_thinker_speaks_the_protocol() {
+        // un moteur minimal qui joue toujours en C1, et note la position reçue
+        let sent = std::env::temp_dir().join(format!("p4_position_{}", std::process::id()));
+        let script = format!(
+            r#"while read cmd args; do
+            case $cmd in
+                p4i) echo "id name C1 bot"; echo p4iok ;;
+                position) echo "$args" > "{}" ;;
+                go) echo "info thinking"; echo "bestmove C1" ;;
+                quit) exit ;;
+            esac
+        done"#,
+            sent.display()
+        );
+        let command = [String::from("sh"), String::from("-c"), script];
+        let thinker =
+            ProcessThinker::new(&command, TurnOrder::default(), Duration::from_millis(100))
+                .unwrap();
+        assert_eq!(thinker.name(), Some("C1 bot"));
+        let mut engine = Engine::new();
+        engine.play_notation("A0").unwrap();
+        let mv = engine.ask(&thinker).unwrap();
+        assert_eq!(mv.to_string(), "C1");
+        engine.play(mv).unwrap();
+        engine.play_notation("A0").unwrap();
+        // après un retour en arrière, le moteur reçoit la vraie partie
+        engine.undo().unwrap();
+        engine.undo().unwrap();
+        engine.play_notation("B0").unwrap();
+        engine.ask(&thinker).unwrap();
+        let position = fs::read_to_string(&sent).unwrap();
+        fs::remove_file(&sent).unwrap();
+        assert_eq!(position.trim(), "size 4x4x4:4 players 2 moves A0 B0");
+        let wrong = parse_notation("B0 A0").unwrap();
+        assert!(matches!(
+            thinker.think_after(engine.plate(), &wrong),
+            Err(P4Error::EngineError(_))
+        ));
+        // C1 est plein : le moteur ne peut plus y jouer
+        engine.play_notation("C1 C1 C1 C1 B0").unwrap();
+        assert!(matches!(engine.ask(&thinker), Err(P4Error::EngineError(_))));
+
+        // un moteur qui n'écoute plus `quit` est tué
+        let stubborn = ["sh", "-c", "read cmd; echo p4iok; sleep 20"].map(String::from);
+        let thinker = ProcessThinker::new(&stubborn, TurnOrder::default(), Duration::ZERO).unwrap();
+        let start = Instant::now();
+        drop(thinker);
+        assert!(start.elapsed() < 2 * ProcessThinker::GRACE);
+
+        let silent = ["sh", "-c", "read cmd"].map(String::from);
+        assert!(matches!(
+            ProcessThinker::new(&silent, TurnOrder::default(), Duration::ZERO),
+            Err(P4Error::EngineError(_))
+        ));
+    }
+}
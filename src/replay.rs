@@ -0,0 +1,177 @@
+use crossterm::event::{Event, KeyCode};
+
+use crate::{
+    engine::Engine, error::P4Error, input::Input, notation::parse_notation, plate::TypeOfDisplay,
+    player::PlayerID, Game,
+};
+
+// Lignes écrites dans le log par `Game`, relues par `moves_from_log`
+pub(crate) const LOG_BEGIN: &str = "Begin of the game";
+pub(crate) const LOG_SEED: &str = "Random seed : ";
+pub(crate) const LOG_LOADED: &str = "Game loaded : ";
+pub(crate) const LOG_PLAYED: &str = "Move played : ";
+pub(crate) const LOG_UNDONE: &str = "Move undone : ";
+pub(crate) const LOG_REDONE: &str = "Move redone : ";
+/// Seule trace des coups dans les anciens logs
+const LEGACY_PLAYED: &str = "Adding player ";
+
+fn last_token(line: &str) -> &str {
+    line.split_whitespace().last().unwrap_or_default()
+}
+
+/// Retrouve les coups de la dernière partie d'un log (`log/my.log`)
+pub fn moves_from_log(log: &str) -> Result<Vec<Input>, P4Error> {
+    let mut moves = Vec::new();
+    let mut legacy = Vec::new();
+    for line in log.lines() {
+        if line.contains(LOG_BEGIN) {
+            moves.clear();
+            legacy.clear();
+        } else if let Some((_, loaded)) = line.split_once(LOG_LOADED) {
+            moves = parse_notation(loaded)?;
+        } else if line.contains(LOG_PLAYED) || line.contains(LOG_REDONE) {
+            moves.extend(parse_notation(last_token(line))?);
+        } else if line.contains(LOG_UNDONE) {
+            moves.pop();
+        } else if line.contains(LEGACY_PLAYED) {
+            // "Adding player P1 at A0 [before]"
+            let mut words = line.rsplit(' ');
+            if let Some(mv) = words.nth(1) {
+                legacy.extend(parse_notation(mv)?);
+            }
+        }
+    }
+    Ok(if moves.is_empty() { legacy } else { moves })
+}
+
+/// Parcourt une partie enregistrée coup par coup
+pub struct Replay {
+    moves: Vec<Input>,
+    /// Le joueur de chaque coup
+    players: Vec<PlayerID>,
+    engine: Engine,
+}
+
+impl Replay {
+    /// `engine` est la partie vide sur laquelle rejouer les coups
+    pub fn new(moves: Vec<Input>, mut engine: Engine) -> Result<Replay, P4Error> {
+        let mut players = Vec::with_capacity(moves.len());
+        for mv in moves.iter() {
+            players.push(engine.current_player());
+            engine.play(mv.clone())?;
+        }
+        while engine.undo().is_ok() {}
+        Ok(Replay {
+            moves,
+            players,
+            engine,
+        })
+    }
+
+    /// Nombre de coups déjà rejoués
+    pub fn position(&self) -> usize {
+        self.engine.history().len()
+    }
+
+    pub fn forward(&mut self) -> bool {
+        self.engine.redo().is_ok()
+    }
+
+    pub fn backward(&mut self) -> bool {
+        self.engine.undo().is_ok()
+    }
+
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    fn display(&self) -> Result<(), P4Error> {
+        let plt = self.engine.plate();
+        plt.clear_plot();
+        plt.plot();
+        let n = self.position();
+        let message = format!("Coup {}/{}", n, self.moves.len());
+        Game::message_display(format!("{:<20}", message).as_str())?;
+        match n {
+            0 => Game::history_display(PlayerID::Empty, "début de la partie")?,
+            n => Game::log_placement_display(self.players[n - 1], self.moves[n - 1].clone())?,
+        }
+        Ok(())
+    }
+
+    /// Flèches gauche/droite pour reculer/avancer, `s` change de vue, `Esc` ou `q` quitte
+    pub fn run(&mut self, display: TypeOfDisplay) -> Result<(), P4Error> {
+        self.engine.plate().type_of_display.set(display);
+        crossterm::terminal::enable_raw_mode()?;
+        self.display()?;
+        loop {
+            let key_ev = match crossterm::event::read()? {
+                Event::Key(k) => k,
+                _ => continue,
+            };
+            match key_ev.code {
+                KeyCode::Right => {
+                    self.forward();
+                }
+                KeyCode::Left => {
+                    self.backward();
+                }
+                KeyCode::Home => while self.backward() {},
+                KeyCode::End => while self.forward() {},
+                KeyCode::Char('s') => self.engine.plate().switch_view(),
+                KeyCode::Esc | KeyCode::Char('q') => break,
+                _ => continue,
+            }
+            self.display()?;
+        }
+        crossterm::terminal::disable_raw_mode()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_moves_follow_undo_and_redo() {
+        let log = "\
+21:05:37 - INFO: Begin of the game
+21:06:13 - INFO: Move played : A1
+21:06:13 - INFO: Move played : D1
+21:06:20 - INFO: Move undone : D1
+21:06:20 - INFO: Move undone : A1
+21:06:21 - INFO: Move redone : A1
+21:06:22 - INFO: Move played : B2
+21:06:25 - INFO: End of the game";
+        assert_eq!(
+            moves_from_log(log).unwrap(),
+            parse_notation("A1 B2").unwrap()
+        );
+    }
+
+    #[test]
+    fn legacy_log_is_understood() {
+        let log = "\
+21:05:37 - INFO: Begin of the game
+21:06:13 - DEBUG: Adding player P1 at A1 [before]
+21:06:13 - DEBUG: Adding player P2 at D1 [before]";
+        assert_eq!(
+            moves_from_log(log).unwrap(),
+            parse_notation("A1 D1").unwrap()
+        );
+    }
+
+    #[test]
+    fn replay_steps_both_ways() {
+        let mut replay = Replay::new(parse_notation("A0 B0 A0").unwrap(), Engine::new()).unwrap();
+        assert_eq!(replay.position(), 0);
+        assert!(!replay.backward());
+        assert!(replay.forward() && replay.forward() && replay.forward());
+        assert!(!replay.forward());
+        assert_eq!(replay.engine().plate().get_cell((0, 0, 1)), PlayerID::P1);
+        assert!(replay.backward());
+        assert_eq!(replay.engine().plate().get_cell((0, 0, 1)), PlayerID::Empty);
+        assert_eq!(replay.position(), 2);
+    }
+}
@@ -0,0 +1,782 @@
+//! Résolution exacte du cube standard : recherche alpha-beta complète, avec une
+//! table de transposition indexée par hachage de Zobrist. Les 8 symétries du
+//! carré de base (rotations et miroirs, la gravité interdit de toucher à la
+//! hauteur) partagent la même entrée. Un livre d'ouvertures calculé à l'avance
+//! évite de refaire les recherches les plus longues, celles du début de partie.
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    bitboard::{Bitboard, LINE_MASKS},
+    engine::Engine,
+    error::P4Error,
+    input::Input,
+    notation::{parse_notation, to_notation},
+    plate::{Outcome, Plate},
+    player::{PlayerID, TurnOrder},
+};
+
+const CELLS: u8 = 64;
+/// Le score d'une victoire obtenue quand le cube compte `tokens` jetons :
+/// plus elle est rapide, plus il est grand. Il ne dépend que de la position,
+/// ce qui permet de le garder dans la table de transposition.
+/// Au-delà du cube plein, aucune victoire n'est plus possible : le score tombe à 0
+const fn win_score(tokens: u8) -> i8 {
+    (CELLS + 1).saturating_sub(tokens) as i8
+}
+
+const fn mix(mut x: u64) -> u64 {
+    // splitmix64
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+const fn compute_zobrist() -> [[u64; 64]; 2] {
+    let mut keys = [[0; 64]; 2];
+    let mut i = 0;
+    while i < 128 {
+        keys[i / 64][i % 64] = mix(i as u64);
+        i += 1;
+    }
+    keys
+}
+
+/// Une clé par joueur et par case
+const ZOBRIST: [[u64; 64]; 2] = compute_zobrist();
+
+/// L'image du pilier (x, y) par la symétrie `s` du carré
+const fn transform(s: usize, x: u8, y: u8) -> (u8, u8) {
+    match s {
+        0 => (x, y),
+        1 => (3 - y, x),
+        2 => (3 - x, 3 - y),
+        3 => (y, 3 - x),
+        4 => (3 - x, y),
+        5 => (x, 3 - y),
+        6 => (y, x),
+        _ => (3 - y, 3 - x),
+    }
+}
+
+const fn compute_symmetries() -> ([[u8; 16]; 8], [[u8; 16]; 8]) {
+    let mut sym = [[0; 16]; 8];
+    let mut inv = [[0; 16]; 8];
+    let mut s = 0;
+    while s < 8 {
+        let mut p = 0;
+        while p < 16 {
+            let (x, y) = transform(s, p % 4, p / 4);
+            let q = x + 4 * y;
+            sym[s][p as usize] = q;
+            inv[s][q as usize] = p;
+            p += 1;
+        }
+        s += 1;
+    }
+    (sym, inv)
+}
+
+const SYMMETRIES: ([[u8; 16]; 8], [[u8; 16]; 8]) = compute_symmetries();
+/// `SYM[s][pilier]` : le pilier vu dans la symétrie `s`
+const SYM: [[u8; 16]; 8] = SYMMETRIES.0;
+/// La réciproque de `SYM`
+const INV: [[u8; 16]; 8] = SYMMETRIES.1;
+
+/// Le hachage de la position dans chacune des 8 symétries
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Hashes([u64; 8]);
+
+impl Hashes {
+    fn of(bb: &Bitboard) -> Hashes {
+        let mut res = Hashes::default();
+        for cell in 0..CELLS {
+            match bb.get_cell(cell) {
+                PlayerID::Empty => {}
+                player => res = res.with(cell, player),
+            }
+        }
+        res
+    }
+
+    fn with(mut self, cell: u8, player: PlayerID) -> Hashes {
+        let (pillar, level) = (cell % 16, cell / 16);
+        for (s, h) in self.0.iter_mut().enumerate() {
+            *h ^= ZOBRIST[player.index()][(SYM[s][pillar as usize] + 16 * level) as usize];
+        }
+        self
+    }
+
+    /// La clé commune aux positions symétriques, et la symétrie qui y mène
+    fn canonical(&self) -> (u64, usize) {
+        let (s, h) = self
+            .0
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, h)| **h)
+            .expect("There are 8 symmetries");
+        (*h, s)
+    }
+}
+
+/// La valeur théorique d'une position pour le joueur qui a le trait,
+/// avec le nombre de coups (des deux joueurs) avant la fin
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Value {
+    Win(u8),
+    Draw,
+    Loss(u8),
+}
+
+impl Value {
+    fn from_score(score: i8, tokens: u8) -> Value {
+        match score {
+            0 => Value::Draw,
+            s if s > 0 => Value::Win(CELLS + 1 - s as u8 - tokens),
+            s => Value::Loss(CELLS + 1 - (-s) as u8 - tokens),
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Win(n) => write!(f, "gagné en {} coups", n),
+            Value::Draw => write!(f, "nul"),
+            Value::Loss(n) => write!(f, "perdu en {} coups", n),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Solution {
+    pub value: Value,
+    pub best: Input,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+    key: u64,
+    score: i8,
+    bound: Bound,
+    /// Le meilleur pilier, vu dans la position canonique
+    best: u8,
+}
+
+/// Une position du livre : le score et le meilleur coup dans la position canonique
+type BookMove = (i8, u8);
+
+/// Les positions résolues à l'avance, une par ligne : `<score> <coup> <partie...>`.
+/// Le score est celui de la table de transposition, du point de vue du joueur qui a le trait.
+#[derive(Clone, Debug, Default)]
+pub struct OpeningBook {
+    entries: HashMap<u64, BookMove>,
+    /// Le nombre de jetons de la plus longue position du livre
+    max_tokens: u8,
+}
+
+impl OpeningBook {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn insert(&mut self, moves: &[Input], score: i8, best: Input) -> Result<(), P4Error> {
+        let bb = Bitboard::from(Engine::from_notation(&to_notation(moves))?.plate());
+        let (key, s) = Hashes::of(&bb).canonical();
+        let pillar = pillar_of(&best)?;
+        self.entries.insert(key, (score, SYM[s][pillar as usize]));
+        self.max_tokens = self.max_tokens.max(moves.len() as u8);
+        Ok(())
+    }
+
+    fn probe(&self, tokens: u8, hashes: &Hashes) -> Option<(i8, u8)> {
+        if tokens > self.max_tokens {
+            return None;
+        }
+        let (key, s) = hashes.canonical();
+        self.entries
+            .get(&key)
+            .map(|(score, best)| (*score, INV[s][*best as usize]))
+    }
+}
+
+impl std::str::FromStr for OpeningBook {
+    type Err = P4Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut book = OpeningBook::default();
+        for line in s.lines().filter(|l| !l.trim().is_empty()) {
+            let err = || P4Error::InvalidNotation(line.to_owned());
+            let mut words = line.splitn(3, ' ');
+            let (Some(score), Some(best)) = (words.next(), words.next()) else {
+                return Err(err());
+            };
+            let score = score.parse().map_err(|_| err())?;
+            let best = parse_notation(best)?.pop().ok_or_else(err)?;
+            book.insert(
+                &parse_notation(words.next().unwrap_or_default())?,
+                score,
+                best,
+            )?;
+        }
+        Ok(book)
+    }
+}
+
+/// Une ligne du livre d'ouvertures
+#[derive(Clone, Debug, PartialEq)]
+pub struct BookEntry {
+    pub moves: Vec<Input>,
+    pub score: i8,
+    pub best: Input,
+}
+
+impl std::fmt::Display for BookEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.score, self.best)?;
+        if !self.moves.is_empty() {
+            write!(f, " {}", to_notation(&self.moves))?;
+        }
+        Ok(())
+    }
+}
+
+fn pillar_of(mv: &Input) -> Result<u8, P4Error> {
+    use crate::coord::Coord;
+    let (x, y) = mv.to_xy_coord();
+    if x < 4 && y < 4 {
+        Ok((x + 4 * y) as u8)
+    } else {
+        Err(P4Error::InvalidMove(mv.clone()))
+    }
+}
+
+/// Le joueur `player` gagne-t-il en posant sur `cell` ?
+fn wins_with(board: u64, cell: u8) -> bool {
+    let board = board | 1 << cell;
+    Bitboard::lines_through(cell).any(|l| board & LINE_MASKS[l] == LINE_MASKS[l])
+}
+
+/// La case où tomberait un jeton sur `pillar`
+fn landing(bb: &Bitboard, pillar: u8) -> Option<u8> {
+    bb.playable(pillar)
+        .then(|| pillar + 16 * bb.get_height(pillar))
+}
+
+pub struct Solver {
+    table: Vec<Option<Entry>>,
+    book: OpeningBook,
+    /// Temps maximal d'une résolution
+    budget: Option<Duration>,
+    deadline: Option<Instant>,
+    nodes: u64,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Solver::new(20)
+    }
+}
+
+impl Solver {
+    /// Une table de transposition de `2^bits` entrées
+    pub fn new(bits: u8) -> Solver {
+        Solver {
+            table: vec![None; 1 << bits],
+            book: OpeningBook::default(),
+            budget: None,
+            deadline: None,
+            nodes: 0,
+        }
+    }
+
+    pub fn with_book(self, book: OpeningBook) -> Solver {
+        Solver { book, ..self }
+    }
+
+    /// Abandonne une résolution qui dure plus de `budget`
+    pub fn with_budget(self, budget: Duration) -> Solver {
+        Solver {
+            budget: Some(budget),
+            ..self
+        }
+    }
+
+    /// Le nombre de positions visitées par la dernière résolution
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    /// La valeur de la position et le meilleur coup pour le joueur qui a le trait
+    pub fn solve(&mut self, plt: &Plate) -> Result<Solution, P4Error> {
+        if !plt.dimensions().is_standard() {
+            return Err(P4Error::UnsupportedDimensions(plt.dimensions()));
+        }
+        let dims = plt.dimensions();
+        for (x, y, h) in itertools::iproduct!(0..dims.width(), 0..dims.depth(), 0..dims.height()) {
+            if let PlayerID::P3 | PlayerID::P4 = plt.get_cell((x, y, h)) {
+                return Err(P4Error::UnsupportedPlayers);
+            }
+        }
+        if plt.outcome() != Outcome::Ongoing {
+            return Err(P4Error::GameOver);
+        }
+        let mut bb = Bitboard::from(plt);
+        let tokens = bb.occupied().count_ones() as u8;
+        let me = if tokens.is_multiple_of(2) {
+            PlayerID::P1
+        } else {
+            PlayerID::P2
+        };
+        self.nodes = 0;
+        self.deadline = self.budget.map(|b| Instant::now() + b);
+        let hashes = Hashes::of(&bb);
+        let (score, best) = self
+            .root(&mut bb, hashes, me, tokens)
+            .ok_or(P4Error::Unsolved)?;
+        let solution = Solution {
+            value: Value::from_score(score, tokens),
+            best: Input::from(best),
+        };
+        log::debug!(
+            "Solved in {} nodes : {} ({})",
+            self.nodes,
+            solution.best,
+            solution.value
+        );
+        Ok(solution)
+    }
+
+    fn slot(&self, key: u64) -> usize {
+        (key as usize) & (self.table.len() - 1)
+    }
+
+    fn lookup(&self, hashes: &Hashes) -> Option<(Entry, usize)> {
+        let (key, s) = hashes.canonical();
+        self.table[self.slot(key)]
+            .filter(|e| e.key == key)
+            .map(|e| (e, s))
+    }
+
+    fn store(&mut self, hashes: &Hashes, score: i8, bound: Bound, best: u8) {
+        let (key, s) = hashes.canonical();
+        let slot = self.slot(key);
+        self.table[slot] = Some(Entry {
+            key,
+            score,
+            bound,
+            best: SYM[s][best as usize],
+        });
+    }
+
+    /// Les coups à essayer, les plus prometteurs d'abord. Un coup qui offre
+    /// une victoire à l'adversaire juste au-dessus n'est gardé qu'en dernier recours
+    fn candidates(&self, bb: &Bitboard, me: PlayerID, hint: Option<u8>) -> Vec<u8> {
+        let mine = bb.get_player_board(me);
+        let theirs = bb.get_player_board(TurnOrder::default().next(me));
+        let mut moves: Vec<(i32, u8)> = (0..16)
+            .filter_map(|p| landing(bb, p).map(|c| (p, c)))
+            .map(|(p, cell)| {
+                let mut score: i32 = Bitboard::lines_through(cell)
+                    .map(|l| LINE_MASKS[l])
+                    .filter(|m| theirs & m == 0)
+                    .map(|m| 1 << (mine & m).count_ones())
+                    .sum();
+                if cell + 16 < CELLS && wins_with(theirs, cell + 16) {
+                    score -= 1000;
+                }
+                if Some(p) == hint {
+                    score += 10_000;
+                }
+                (score, p)
+            })
+            .collect();
+        moves.sort_by_key(|(score, _)| -score);
+        moves.into_iter().map(|(_, p)| p).collect()
+    }
+
+    /// Les coups imposés : une victoire immédiate, ou la parade d'une menace.
+    /// `Err` donne le score si la position est déjà jouée d'avance.
+    fn forced(bb: &Bitboard, me: PlayerID, tokens: u8) -> Result<Option<u8>, i8> {
+        let mine = bb.get_player_board(me);
+        let theirs = bb.get_player_board(TurnOrder::default().next(me));
+        let landings: Vec<(u8, u8)> = (0..16)
+            .filter_map(|p| landing(bb, p).map(|c| (p, c)))
+            .collect();
+        if let Some((pillar, _)) = landings.iter().find(|(_, c)| wins_with(mine, *c)) {
+            return Ok(Some(*pillar));
+        }
+        let mut threat = None;
+        for (pillar, cell) in landings {
+            if wins_with(theirs, cell) {
+                if threat.is_some() {
+                    // deux menaces, une seule parade
+                    return Err(-win_score(tokens + 2));
+                }
+                threat = Some(pillar);
+            }
+        }
+        Ok(threat)
+    }
+
+    fn root(
+        &mut self,
+        bb: &mut Bitboard,
+        hashes: Hashes,
+        me: PlayerID,
+        tokens: u8,
+    ) -> Option<(i8, u8)> {
+        if let Some((score, best)) = self.book.probe(tokens, &hashes) {
+            return Some((score, best));
+        }
+        let moves = match Self::forced(bb, me, tokens) {
+            Ok(Some(p)) if wins_with(bb.get_player_board(me), landing(bb, p)?) => {
+                return Some((win_score(tokens + 1), p))
+            }
+            Ok(Some(p)) => vec![p],
+            // tout est perdu, autant parer une des menaces
+            Err(score) => return Some((score, self.candidates(bb, me, None)[0])),
+            Ok(None) => self.candidates(bb, me, None),
+        };
+        let mut alpha = -win_score(0) - 1;
+        let mut best = moves[0];
+        for pillar in moves {
+            let cell = bb.play(pillar, me).ok()?;
+            let child = hashes.with(cell, me);
+            let opponent = TurnOrder::default().next(me);
+            let score = self.search(bb, child, opponent, tokens + 1, -win_score(0) - 1, -alpha);
+            bb.undo(pillar);
+            let score = -score?;
+            if score > alpha {
+                alpha = score;
+                best = pillar;
+            }
+        }
+        Some((alpha, best))
+    }
+
+    fn search(
+        &mut self,
+        bb: &mut Bitboard,
+        hashes: Hashes,
+        me: PlayerID,
+        tokens: u8,
+        mut alpha: i8,
+        mut beta: i8,
+    ) -> Option<i8> {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(4096) && self.deadline.is_some_and(|d| Instant::now() >= d) {
+            return None;
+        }
+        if tokens == CELLS {
+            return Some(0);
+        }
+        let forced = match Self::forced(bb, me, tokens) {
+            Ok(Some(p)) if wins_with(bb.get_player_board(me), landing(bb, p)?) => {
+                return Some(win_score(tokens + 1))
+            }
+            Ok(forced) => forced,
+            Err(score) => return Some(score),
+        };
+        // au mieux on gagne au coup d'après, au pire l'adversaire gagne au sien
+        beta = beta.min(win_score(tokens + 3));
+        alpha = alpha.max(-win_score(tokens + 2));
+        if alpha >= beta {
+            return Some(beta);
+        }
+        if let Some((score, _)) = self.book.probe(tokens, &hashes) {
+            return Some(score);
+        }
+
+        let mut hint = None;
+        if let Some((entry, s)) = self.lookup(&hashes) {
+            match entry.bound {
+                Bound::Exact => return Some(entry.score),
+                Bound::Lower => alpha = alpha.max(entry.score),
+                Bound::Upper => beta = beta.min(entry.score),
+            }
+            if alpha >= beta {
+                return Some(entry.score);
+            }
+            hint = Some(INV[s][entry.best as usize]);
+        }
+
+        let moves = match forced {
+            Some(p) => vec![p],
+            None => self.candidates(bb, me, hint),
+        };
+        let opponent = TurnOrder::default().next(me);
+        let alpha_start = alpha;
+        let mut best = moves[0];
+        let mut best_score = -win_score(0) - 1;
+        for pillar in moves {
+            let cell = bb.play(pillar, me).ok()?;
+            let score = self.search(
+                bb,
+                hashes.with(cell, me),
+                opponent,
+                tokens + 1,
+                -beta,
+                -alpha,
+            );
+            bb.undo(pillar);
+            let score = -score?;
+            if score > best_score {
+                best_score = score;
+                best = pillar;
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        let bound = if best_score <= alpha_start {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.store(&hashes, best_score, bound, best);
+        Some(best_score)
+    }
+
+    /// Résout toutes les positions d'au plus `plies` coups, une seule par
+    /// famille de positions symétriques. Celles qui dépassent le temps
+    /// de résolution sont laissées de côté.
+    pub fn build_book(
+        &mut self,
+        plies: u8,
+        mut progress: impl FnMut(&BookEntry),
+    ) -> Vec<BookEntry> {
+        let mut seen = HashSet::new();
+        let mut todo = vec![Vec::new()];
+        let mut entries = Vec::new();
+        // en largeur, pour que les positions courtes viennent en premier
+        for _ in 0..=plies {
+            let mut next = Vec::new();
+            for moves in todo {
+                let Ok(engine) = Engine::from_notation(&to_notation(&moves)) else {
+                    continue;
+                };
+                let (key, _) = Hashes::of(&Bitboard::from(engine.plate())).canonical();
+                if engine.outcome() != Outcome::Ongoing || !seen.insert(key) {
+                    continue;
+                }
+                match self.solve(engine.plate()) {
+                    Ok(solution) => {
+                        let tokens = moves.len() as u8;
+                        let score = match solution.value {
+                            Value::Win(n) => win_score(tokens + n),
+                            Value::Draw => 0,
+                            Value::Loss(n) => -win_score(tokens + n),
+                        };
+                        let entry = BookEntry {
+                            moves: moves.clone(),
+                            score,
+                            best: solution.best,
+                        };
+                        progress(&entry);
+                        entries.push(entry);
+                    }
+                    Err(e) => log::info!("{} left out of the book : {}", to_notation(&moves), e),
+                }
+                for mv in engine.legal_moves() {
+                    let mut longer = moves.clone();
+                    longer.push(mv);
+                    next.push(longer);
+                }
+            }
+            todo = next;
+        }
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::BotRng;
+    use rand::{seq::SliceRandom, SeedableRng};
+
+    /// Une position tirée au hasard avec `empty` cases libres, où personne n'a
+    /// encore aligné ses jetons : les coups gagnants sont évités tant que possible
+    fn random_position(seed: u64, empty: u8) -> Option<Engine> {
+        let mut rng = BotRng::seed_from_u64(seed);
+        let mut bb = Bitboard::default();
+        let mut moves = Vec::new();
+        let mut me = PlayerID::P1;
+        while CELLS - (moves.len() as u8) > empty {
+            let quiet: Vec<u8> = (0..16)
+                .filter(|p| match bb.play(*p, me) {
+                    Ok(cell) => {
+                        let wins = bb.wins_at(cell);
+                        bb.undo(*p);
+                        !wins
+                    }
+                    Err(_) => false,
+                })
+                .collect();
+            let pillar = *quiet.choose(&mut rng)?;
+            bb.play(pillar, me).ok()?;
+            moves.push(Input::from(pillar));
+            me = TurnOrder::default().next(me);
+        }
+        Engine::from_notation(&to_notation(&moves)).ok()
+    }
+
+    /// Minimax sans aucune optimisation, pour vérifier le solveur
+    fn minimax(bb: &mut Bitboard, me: PlayerID, tokens: u8) -> i8 {
+        if tokens == CELLS {
+            return 0;
+        }
+        let mut best = i8::MIN;
+        for pillar in bb.legal_pillars().collect::<Vec<_>>() {
+            let cell = bb.play(pillar, me).unwrap();
+            let score = if bb.wins_at(cell) {
+                win_score(tokens + 1)
+            } else {
+                -minimax(bb, TurnOrder::default().next(me), tokens + 1)
+            };
+            bb.undo(pillar);
+            best = best.max(score);
+        }
+        best
+    }
+
+    #[test]
+    fn symmetries_are_permutations() {
+        for s in 0..8 {
+            let mut seen = SYM[s];
+            seen.sort();
+            assert_eq!(seen, core::array::from_fn(|i| i as u8));
+            for p in 0..16 {
+                assert_eq!(INV[s][SYM[s][p] as usize], p as u8);
+            }
+        }
+        // un coup et son miroir ont la même clé
+        let a = Hashes::of(&Bitboard::from(
+            Engine::from_notation("A0 B1").unwrap().plate(),
+        ));
+        let b = Hashes::of(&Bitboard::from(
+            Engine::from_notation("D3 C2").unwrap().plate(),
+        ));
+        assert_eq!(a.canonical().0, b.canonical().0);
+    }
+
+    #[test]
+    fn agrees_with_plain_minimax() {
+        let mut solver = Solver::new(16);
+        let mut checked = 0;
+        for seed in 0..40 {
+            let Some(engine) = random_position(seed, 9) else {
+                continue;
+            };
+            let mut bb = Bitboard::from(engine.plate());
+            let me = engine.current_player();
+            let expected = minimax(&mut bb, me, 55);
+            let solution = solver.solve(engine.plate()).unwrap();
+            assert_eq!(
+                solution.value,
+                Value::from_score(expected, 55),
+                "{}",
+                engine.to_notation()
+            );
+            // le coup proposé atteint bien cette valeur
+            let mut after = engine;
+            if let Outcome::Ongoing = after.play(solution.best).unwrap() {
+                let theirs = solver.solve(after.plate()).unwrap().value;
+                let back = match theirs {
+                    Value::Win(n) => Value::Loss(n + 1),
+                    Value::Loss(n) => Value::Win(n + 1),
+                    Value::Draw => Value::Draw,
+                };
+                assert_eq!(back, solution.value);
+            }
+            checked += 1;
+        }
+        assert!(checked > 10);
+    }
+
+    #[test]
+    fn immediate_win_and_forced_loss() {
+        let mut solver = Solver::default();
+        let engine = Engine::from_notation("A0 A1 B0 B1 C0 C1").unwrap();
+        let solution = solver.solve(engine.plate()).unwrap();
+        assert_eq!(solution.value, Value::Win(1));
+        assert_eq!(solution.best.to_string(), "D0");
+        // deux menaces ouvertes : P2 ne peut en parer qu'une
+        // C0 ouvre à la fois D0 et C3
+        let engine = Engine::from_notation("A0 B3 B0 B3 C1 A2 C2 D1 C0").unwrap();
+        assert_eq!(solver.solve(engine.plate()).unwrap().value, Value::Loss(2));
+    }
+
+    #[test]
+    fn nearly_full_cube_is_drawn() {
+        use crate::plate::tests::{filled, DRAWN_PILLARS};
+        use itertools::Itertools;
+        let mut solver = Solver::default();
+        let mut drawn = 0;
+        // un jeton de chaque joueur en moins, P1 a le trait
+        let pairs = (0..16u8)
+            .tuple_combinations()
+            .filter(|(i, j)| DRAWN_PILLARS[*i as usize][3] != DRAWN_PILLARS[*j as usize][3]);
+        for (i, j) in pairs {
+            let mut plt = filled(DRAWN_PILLARS);
+            plt.undo(Input::from(i)).unwrap();
+            plt.undo(Input::from(j)).unwrap();
+            let mut bb = Bitboard::from(&plt);
+            let expected = Value::from_score(minimax(&mut bb, PlayerID::P1, 62), 62);
+            let value = solver.solve(&plt).unwrap().value;
+            assert_eq!(value, expected, "{} {}", i, j);
+            // deux cases libres : P1 gagne tout de suite, ou personne
+            if value == Value::Draw {
+                drawn += 1;
+            } else {
+                assert_eq!(value, Value::Win(1), "{} {}", i, j);
+            }
+        }
+        assert_eq!(drawn, 33);
+    }
+
+    #[test]
+    fn book_round_trip() {
+        let mut solver = Solver::default().with_budget(Duration::from_millis(1));
+        // sans temps pour résoudre, les positions sont laissées de côté
+        assert!(solver.build_book(1, |_| {}).is_empty());
+
+        let engine = random_position(3, 12).unwrap();
+        let moves = engine.history().to_vec();
+        let mut solver = Solver::default();
+        let solution = solver.solve(engine.plate()).unwrap();
+        let tokens = moves.len() as u8;
+        let entry = BookEntry {
+            moves: moves.clone(),
+            score: match solution.value {
+                Value::Win(n) => win_score(tokens + n),
+                Value::Draw => 0,
+                Value::Loss(n) => -win_score(tokens + n),
+            },
+            best: solution.best.clone(),
+        };
+        let book: OpeningBook = entry.to_string().parse().unwrap();
+        assert_eq!(book.len(), 1);
+        let mut with_book = Solver::new(4).with_book(book);
+        assert_eq!(with_book.solve(engine.plate()).unwrap(), solution);
+        assert_eq!(with_book.nodes(), 0);
+        assert!("x A0".parse::<OpeningBook>().is_err());
+    }
+}
@@ -0,0 +1,277 @@
+//! Un bot à règles, entre le hasard et la recherche : il ne rate jamais une
+//! victoire ni une parade en un coup, et sinon vise les lignes les plus ouvertes.
+use std::cell::RefCell;
+
+use rand::{seq::SliceRandom, SeedableRng};
+
+use crate::{
+    coord::Coord,
+    error::P4Error,
+    input::Input,
+    logic::{BotRng, Thinker},
+    plate::Plate,
+    player::{PlayerID, TurnOrder},
+};
+
+/// La règle qui a décidé du coup
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reason {
+    /// Le coup aligne les jetons et gagne
+    Wins,
+    /// Le coup empêche `player` de finir une ligne de `tokens` jetons
+    Blocks { player: PlayerID, tokens: u8 },
+    /// Le coup laisse une seule case libre sur une ligne, qui compte alors `tokens` jetons
+    Threatens { tokens: u8 },
+    /// Le coup est sur `lines` lignes encore libres d'adversaires
+    OpenLines { lines: usize },
+    /// Tous les coups laissent gagner un adversaire juste au-dessus
+    NoSafeMove,
+}
+
+impl std::fmt::Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reason::Wins => write!(f, "gagne la partie"),
+            Reason::Blocks { player, tokens } => {
+                write!(f, "bloque une ligne de {} de {}", tokens, player)
+            }
+            Reason::Threatens { tokens } => write!(f, "menace une ligne de {}", tokens),
+            Reason::OpenLines { lines: 0 } => write!(f, "aucune ligne n'est encore libre ici"),
+            Reason::OpenLines { lines: 1 } => write!(f, "prend une ligne encore libre"),
+            Reason::OpenLines { lines } => write!(f, "prend {} lignes encore libres", lines),
+            Reason::NoSafeMove => write!(f, "tous les coups laissent gagner un adversaire"),
+        }
+    }
+}
+
+pub struct Tactical {
+    pub player_id: PlayerID,
+    players: TurnOrder,
+    /// Départage les coups aussi bons l'un que l'autre
+    rng: RefCell<BotRng>,
+}
+
+impl Tactical {
+    pub fn new(player_id: PlayerID, players: TurnOrder, seed: u64) -> Tactical {
+        Tactical {
+            player_id,
+            players,
+            rng: RefCell::new(BotRng::seed_from_u64(seed)),
+        }
+    }
+
+    /// Les adversaires, du prochain à jouer au dernier
+    fn opponents(&self) -> impl Iterator<Item = PlayerID> + '_ {
+        let mut player = self.player_id;
+        (1..self.players.count()).map(move |_| {
+            player = self.players.next(player);
+            player
+        })
+    }
+
+    /// Un adversaire pourrait-il gagner sur le jeton que `mv` pose ?
+    fn opens_the_pillar(&self, plt: &mut Plate, mv: &Input) -> Result<bool, P4Error> {
+        plt.add_player(mv.clone(), self.player_id)?;
+        let res = self.opponents().any(|p| plt.completes_line(mv, p));
+        plt.undo(mv.clone())?;
+        Ok(res)
+    }
+
+    /// Les lignes par où tomberait le jeton sans jeton adverse, et le poids
+    /// de chacune : elle compte double pour chacun de nos jetons déjà dessus
+    fn open_lines(&self, plt: &Plate, mv: &Input) -> (usize, u32) {
+        let Some(h) = plt.landing_height(mv) else {
+            return (0, 0);
+        };
+        let (x, y) = mv.to_xy_coord();
+        plt.lines_through((x as u8, y as u8, h))
+            .map(|line| line.iter().map(|c| plt.get_cell(*c)))
+            .filter(|cells| {
+                cells
+                    .clone()
+                    .all(|p| p == PlayerID::Empty || p == self.player_id)
+            })
+            .map(|cells| 1 << cells.filter(|p| *p == self.player_id).count())
+            .fold((0, 0), |(lines, weight), w| (lines + 1, weight + w))
+    }
+
+    /// Pourquoi jouer `mv` : la première règle qui s'y applique
+    pub fn explain(&self, plt: &Plate, mv: &Input) -> Reason {
+        let tokens = plt.dimensions().align() - 1;
+        if plt.completes_line(mv, self.player_id) {
+            return Reason::Wins;
+        }
+        if let Some(player) = self.opponents().find(|p| plt.completes_line(mv, *p)) {
+            return Reason::Blocks { player, tokens };
+        }
+        if self.threatens(plt, mv) {
+            return Reason::Threatens { tokens };
+        }
+        Reason::OpenLines {
+            lines: self.open_lines(plt, mv).0,
+        }
+    }
+
+    /// Le jeton posé par `mv` laisserait-il une seule case libre sur une de nos lignes ?
+    fn threatens(&self, plt: &Plate, mv: &Input) -> bool {
+        let Some(h) = plt.landing_height(mv) else {
+            return false;
+        };
+        let (x, y) = mv.to_xy_coord();
+        let before = plt.dimensions().align() as usize - 2;
+        plt.lines_through((x as u8, y as u8, h)).any(|line| {
+            let cells: Vec<PlayerID> = line.iter().map(|c| plt.get_cell(*c)).collect();
+            cells
+                .iter()
+                .all(|p| *p == PlayerID::Empty || *p == self.player_id)
+                && cells.iter().filter(|p| **p == self.player_id).count() == before
+        })
+    }
+
+    /// Le coup choisi et la règle qui l'a choisi
+    pub fn suggest(&self, plt: &Plate) -> Result<(Input, Reason), P4Error> {
+        let moves: Vec<Input> = plt.legal_moves().map(|(mv, _)| mv).collect();
+        if moves.is_empty() {
+            return Err(P4Error::NoLegalMove);
+        }
+        if let Some(mv) = moves
+            .iter()
+            .find(|mv| plt.completes_line(*mv, self.player_id))
+        {
+            return Ok((mv.clone(), Reason::Wins));
+        }
+        for player in self.opponents() {
+            if let Some(mv) = moves.iter().find(|mv| plt.completes_line(*mv, player)) {
+                let tokens = plt.dimensions().align() - 1;
+                return Ok((mv.clone(), Reason::Blocks { player, tokens }));
+            }
+        }
+
+        let mut work = plt.clone();
+        let mut safe = Vec::new();
+        for mv in moves.iter() {
+            if !self.opens_the_pillar(&mut work, mv)? {
+                safe.push(mv.clone());
+            }
+        }
+        if safe.is_empty() {
+            let mv = moves.choose(&mut *self.rng.borrow_mut());
+            return Ok((mv.cloned().ok_or(P4Error::NoLegalMove)?, Reason::NoSafeMove));
+        }
+        let scored: Vec<(Input, (usize, u32))> = safe
+            .into_iter()
+            .map(|mv| {
+                let score = self.open_lines(plt, &mv);
+                (mv, score)
+            })
+            .collect();
+        let best = scored.iter().map(|(_, (_, w))| *w).max().unwrap_or(0);
+        let ties: Vec<&(Input, (usize, u32))> =
+            scored.iter().filter(|(_, (_, w))| *w == best).collect();
+        let (mv, (lines, _)) = ties
+            .choose(&mut *self.rng.borrow_mut())
+            .ok_or(P4Error::NoLegalMove)?;
+        Ok((mv.clone(), Reason::OpenLines { lines: *lines }))
+    }
+}
+
+impl Thinker for Tactical {
+    fn think(&self, plt: &Plate) -> Result<Input, P4Error> {
+        let (mv, reason) = self.suggest(plt)?;
+        log::debug!("Tactical plays {} : {:?}", mv, reason);
+        Ok(mv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{engine::Engine, logic::RBot, notation::parse_notation, plate::Outcome};
+
+    fn suggest(notation: &str, seed: u64) -> (String, Reason) {
+        let engine = Engine::from_notation(notation).unwrap();
+        let bot = Tactical::new(engine.current_player(), TurnOrder::default(), seed);
+        let (mv, reason) = bot.suggest(engine.plate()).unwrap();
+        (mv.to_string(), reason)
+    }
+
+    #[test]
+    fn wins_then_blocks() {
+        assert_eq!(
+            suggest("A0 A1 B0 B1 C0 C1", 0),
+            (String::from("D0"), Reason::Wins)
+        );
+        let (mv, reason) = suggest("A0 A1 B0 B2 C0", 0);
+        assert_eq!(mv, "D0");
+        assert_eq!(
+            reason,
+            Reason::Blocks {
+                player: PlayerID::P1,
+                tokens: 3
+            }
+        );
+    }
+
+    #[test]
+    fn does_not_fill_under_a_threat() {
+        // P2 a A0, B0 et C0 au deuxième étage : jouer en D0 lui offre D0 juste au-dessus
+        for seed in 0..20 {
+            let (mv, reason) = suggest("A0 B0 C0 A0 D3 B0 D3 C0", seed);
+            assert_ne!(mv, "D0");
+            assert!(matches!(reason, Reason::OpenLines { .. }));
+        }
+    }
+
+    #[test]
+    fn opens_in_a_corner() {
+        // les coins et le centre du cube ont 7 lignes, mais au sol seuls les coins
+        for seed in 0..20 {
+            let (mv, reason) = suggest("", seed);
+            assert!(["A0", "D0", "A3", "D3"].contains(&mv.as_str()), "{}", mv);
+            assert_eq!(reason, Reason::OpenLines { lines: 7 });
+        }
+    }
+
+    #[test]
+    fn moves_are_explained() {
+        let engine = Engine::from_notation("A0 A1 B0 B2").unwrap();
+        let bot = Tactical::new(PlayerID::P1, TurnOrder::default(), 0);
+        let explain = |mv: &str| bot.explain(engine.plate(), &parse_notation(mv).unwrap()[0]);
+        assert_eq!(explain("C0"), Reason::Threatens { tokens: 3 });
+        assert_eq!(explain("D3"), Reason::OpenLines { lines: 7 });
+        let engine = Engine::from_notation("A0 A1 B0 B1 C0 C1").unwrap();
+        let bot = Tactical::new(PlayerID::P1, TurnOrder::default(), 0);
+        assert_eq!(
+            bot.explain(engine.plate(), &parse_notation("D0").unwrap()[0]),
+            Reason::Wins
+        );
+        assert_eq!(
+            bot.explain(engine.plate(), &parse_notation("D1").unwrap()[0]),
+            Reason::Blocks {
+                player: PlayerID::P2,
+                tokens: 3
+            }
+        );
+    }
+
+    #[test]
+    fn beats_random_play() {
+        let mut wins = 0;
+        for seed in 0..20 {
+            let tactical = Tactical::new(PlayerID::P2, TurnOrder::default(), seed);
+            let random = RBot::new(seed);
+            let mut engine = Engine::new();
+            while engine.outcome() == Outcome::Ongoing {
+                let mv = match engine.current_player() {
+                    PlayerID::P1 => engine.ask(&random),
+                    _ => engine.ask(&tactical),
+                };
+                engine.play(mv.unwrap()).unwrap();
+            }
+            if let Outcome::Win(PlayerID::P2, _) = engine.outcome() {
+                wins += 1;
+            }
+        }
+        assert!(wins >= 18, "{} wins out of 20", wins);
+    }
+}
@@ -0,0 +1,347 @@
+//! Tournoi sans affichage entre deux joueurs : les parties s'enchaînent en
+//! alternant celui qui commence, les résultats sont exportés en CSV ou JSON.
+use std::fmt::Write;
+
+use crate::{
+    config::PlayerKind,
+    dimensions::Dimensions,
+    engine::Engine,
+    error::P4Error,
+    plate::Outcome,
+    player::{PlayerID, TurnOrder},
+};
+
+/// Une partie du tournoi, les joueurs sont désignés par leur indice dans `Tournament::players`
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameRecord {
+    /// Celui qui a commencé, avec la couleur `P1`
+    pub first: usize,
+    /// `None` pour un match nul
+    pub winner: Option<usize>,
+    /// La raison du forfait du perdant, s'il n'a pas fini la partie
+    pub forfeit: Option<String>,
+    pub notation: String,
+    pub length: usize,
+}
+
+pub struct Tournament {
+    players: [PlayerKind; 2],
+    dims: Dimensions,
+    seed: u64,
+}
+
+impl Tournament {
+    pub fn new(players: [PlayerKind; 2], dims: Dimensions, seed: u64) -> Tournament {
+        Tournament {
+            players,
+            dims,
+            seed,
+        }
+    }
+
+    pub fn players(&self) -> &[PlayerKind; 2] {
+        &self.players
+    }
+
+    /// Joue la partie `n` : les joueurs commencent chacun leur tour
+    pub fn play(&self, n: usize) -> Result<GameRecord, P4Error> {
+        let first = n % 2;
+        let order = TurnOrder::default();
+        let seed = self.seed.wrapping_add(2 * n as u64);
+        // l'entrant de chaque couleur
+        let entrants = [first, 1 - first];
+        let thinkers = [PlayerID::P1, PlayerID::P2]
+            .into_iter()
+            .zip(entrants)
+            .map(|(color, entrant)| {
+                let seed = seed.wrapping_add(color.index() as u64);
+                self.players[entrant].build(color, order, seed, None)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut engine = Engine::with_players(self.dims, order);
+        let mut forfeit = None;
+        while engine.outcome() == Outcome::Ongoing {
+            let mover = engine.current_player();
+            let res = engine
+                .ask(thinkers[mover.index()].as_ref())
+                .and_then(|mv| engine.play(mv));
+            if let Err(e) = res {
+                log::warn!(
+                    "{} forfeits game {} : {}",
+                    self.players[entrants[mover.index()]],
+                    n,
+                    e
+                );
+                forfeit = Some((entrants[mover.index()], e.to_string()));
+                break;
+            }
+        }
+        let winner = match (&forfeit, engine.outcome()) {
+            (Some((loser, _)), _) => Some(1 - loser),
+            (None, Outcome::Win(color, _)) => Some(entrants[color.index()]),
+            _ => None,
+        };
+        Ok(GameRecord {
+            first,
+            winner,
+            forfeit: forfeit.map(|(_, reason)| reason),
+            notation: engine.to_notation(),
+            length: engine.history().len(),
+        })
+    }
+
+    /// Joue `games` parties, `progress` est appelé après chacune
+    pub fn run(
+        &self,
+        games: usize,
+        mut progress: impl FnMut(&GameRecord),
+    ) -> Result<Standings, P4Error> {
+        let mut standings = Standings::new(self.players.clone());
+        for n in 0..games {
+            let record = self.play(n)?;
+            progress(&record);
+            standings.games.push(record);
+        }
+        Ok(standings)
+    }
+}
+
+/// Le bilan du tournoi, vu du premier joueur pour l'Elo
+#[derive(Clone, Debug)]
+pub struct Standings {
+    players: [PlayerKind; 2],
+    pub games: Vec<GameRecord>,
+}
+
+impl Standings {
+    pub fn new(players: [PlayerKind; 2]) -> Standings {
+        Standings {
+            players,
+            games: Vec::new(),
+        }
+    }
+
+    pub fn wins(&self, player: usize) -> usize {
+        self.games
+            .iter()
+            .filter(|g| g.winner == Some(player))
+            .count()
+    }
+
+    pub fn draws(&self) -> usize {
+        self.games.iter().filter(|g| g.winner.is_none()).count()
+    }
+
+    pub fn average_length(&self) -> f64 {
+        if self.games.is_empty() {
+            return 0.;
+        }
+        self.games.iter().map(|g| g.length).sum::<usize>() as f64 / self.games.len() as f64
+    }
+
+    /// Les points du premier joueur, une victoire vaut 1 et un nul 1/2
+    pub fn score(&self) -> f64 {
+        self.wins(0) as f64 + self.draws() as f64 / 2.
+    }
+
+    /// L'écart d'Elo estimé entre le premier et le second joueur,
+    /// `None` sans partie ou quand l'un a tout gagné
+    pub fn elo_difference(&self) -> Option<f64> {
+        let ratio = self.score() / self.games.len() as f64;
+        (ratio > 0. && ratio < 1.).then(|| 400. * (ratio / (1. - ratio)).log10())
+    }
+
+    /// Une ligne par partie, les joueurs y sont numérotés 1 et 2 comme `--p1` et `--p2`
+    pub fn to_csv(&self) -> String {
+        let mut res = String::from("game,first,winner,length,forfeit,moves\n");
+        for (n, g) in self.games.iter().enumerate() {
+            let fields = [
+                (n + 1).to_string(),
+                (g.first + 1).to_string(),
+                g.winner
+                    .map_or_else(|| String::from("draw"), |p| (p + 1).to_string()),
+                g.length.to_string(),
+                g.forfeit.clone().unwrap_or_default(),
+                g.notation.clone(),
+            ];
+            res += &fields
+                .iter()
+                .map(|f| csv_field(f))
+                .collect::<Vec<_>>()
+                .join(",");
+            res.push('\n');
+        }
+        res
+    }
+
+    /// Le bilan puis le détail des parties, numérotées comme dans `to_csv`
+    pub fn to_json(&self) -> String {
+        let mut res = String::from("{\n");
+        let players: Vec<String> = self
+            .players
+            .iter()
+            .map(|p| json_string(&p.to_string()))
+            .collect();
+        let elo = match self.elo_difference() {
+            Some(elo) => format!("{:.1}", elo),
+            None => String::from("null"),
+        };
+        let _ = writeln!(res, "  \"players\": [{}],", players.join(", "));
+        let _ = writeln!(res, "  \"games\": {},", self.games.len());
+        let _ = writeln!(res, "  \"wins\": [{}, {}],", self.wins(0), self.wins(1));
+        let _ = writeln!(res, "  \"draws\": {},", self.draws());
+        let _ = writeln!(res, "  \"average_length\": {:.2},", self.average_length());
+        let _ = writeln!(res, "  \"elo_difference\": {},", elo);
+        res += "  \"results\": [";
+        for (n, g) in self.games.iter().enumerate() {
+            let forfeit = match &g.forfeit {
+                Some(reason) => json_string(reason),
+                None => String::from("null"),
+            };
+            let winner = match g.winner {
+                Some(p) => (p + 1).to_string(),
+                None => String::from("null"),
+            };
+            let _ = write!(
+                res,
+                "{}\n    {{\"game\": {}, \"first\": {}, \"winner\": {}, \"length\": {}, \"forfeit\": {}, \"moves\": {}}}",
+                if n == 0 { "" } else { "," },
+                n + 1,
+                g.first + 1,
+                winner,
+                g.length,
+                forfeit,
+                json_string(&g.notation),
+            );
+        }
+        res += "\n  ]\n}\n";
+        res
+    }
+}
+
+impl std::fmt::Display for Standings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} parties", self.games.len())?;
+        for p in 0..2 {
+            writeln!(
+                f,
+                "  {:<30} {} victoires",
+                self.players[p].to_string(),
+                self.wins(p)
+            )?;
+        }
+        writeln!(f, "  {:<30} {}", "nuls", self.draws())?;
+        writeln!(f, "Longueur moyenne : {:.1} coups", self.average_length())?;
+        match self.elo_difference() {
+            Some(elo) => write!(f, "Écart d'Elo estimé : {:+.0}", elo),
+            None => write!(f, "Écart d'Elo estimé : indéterminé"),
+        }
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut res = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => res += "\\\"",
+            '\\' => res += "\\\\",
+            '\n' => res += "\\n",
+            c if c.is_control() => {
+                let _ = write!(res, "\\u{:04x}", c as u32);
+            }
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(first: usize, winner: Option<usize>, length: usize) -> GameRecord {
+        GameRecord {
+            first,
+            winner,
+            forfeit: None,
+            notation: String::new(),
+            length,
+        }
+    }
+
+    #[test]
+    fn standings_are_counted() {
+        let players = ["negamax:1".parse().unwrap(), "negamax:2".parse().unwrap()];
+        let mut standings = Standings::new(players);
+        assert_eq!(standings.elo_difference(), None);
+        standings.games = vec![
+            record(0, Some(0), 10),
+            record(1, Some(0), 20),
+            record(0, None, 64),
+            record(1, Some(1), 30),
+        ];
+        assert_eq!(standings.wins(0), 2);
+        assert_eq!(standings.draws(), 1);
+        assert_eq!(standings.average_length(), 31.);
+        // 2,5 points sur 4, soit 62,5 %
+        let elo = standings.elo_difference().unwrap();
+        assert!((elo - 88.7).abs() < 0.1, "{}", elo);
+        assert_eq!(standings.to_csv().lines().count(), 5);
+        assert!(standings.to_json().contains("\"wins\": [2, 1],"));
+    }
+
+    #[test]
+    fn starts_alternate() {
+        let players = [
+            "negamax:1:100".parse().unwrap(),
+            "negamax:2:100".parse().unwrap(),
+        ];
+        let tournament = Tournament::new(players, Dimensions::STANDARD, 0);
+        let mut seen = 0;
+        let standings = tournament.run(2, |_| seen += 1).unwrap();
+        assert_eq!(seen, 2);
+        assert_eq!(standings.games[0].first, 0);
+        assert_eq!(standings.games[1].first, 1);
+        for g in standings.games.iter() {
+            assert_eq!(
+                Engine::from_notation(&g.notation).unwrap().history().len(),
+                g.length
+            );
+        }
+        assert_eq!(standings.wins(0) + standings.wins(1) + standings.draws(), 2);
+    }
+
+    #[test]
+    fn any_seed_is_accepted() {
+        let players = [PlayerKind::Random, PlayerKind::Random];
+        let tournament = Tournament::new(players, Dimensions::STANDARD, u64::MAX);
+        assert!(tournament.play(0).unwrap().forfeit.is_none());
+    }
+
+    #[test]
+    fn unsupported_player_forfeits() {
+        let dims: Dimensions = "5x5x5:4".parse().unwrap();
+        let players = [PlayerKind::Random, "negamax:1".parse().unwrap()];
+        // negamax ne joue pas sur ce plateau : il perd dès son premier tour
+        let record = Tournament::new(players, dims, 0).play(1).unwrap();
+        assert_eq!(record.winner, Some(0));
+        assert_eq!(record.length, 0);
+        assert!(record.forfeit.is_some());
+    }
+
+    #[test]
+    fn fields_are_escaped() {
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(json_string("dit \"non\"\n"), "\"dit \\\"non\\\"\\n\"");
+    }
+}